@@ -0,0 +1,30 @@
+//! Timing harness shared by the benchmarks.
+
+use std::time::{Duration, Instant};
+
+/// Number of timed runs of each benchmark.
+const SAMPLES: usize = 10;
+
+/// Runs `f` once to warm up and then `SAMPLES` times, prints the fastest, median and slowest
+/// running times, and returns the median one and the result, which checks that the work is not
+/// optimized away.
+pub fn bench(name: &str, mut f: impl FnMut() -> f64) -> (Duration, f64) {
+    let result = f();
+    let mut times = (0..SAMPLES)
+        .map(|_| {
+            let start = Instant::now();
+            assert_eq!(f(), result);
+            start.elapsed()
+        })
+        .collect::<Vec<_>>();
+    times.sort();
+    let median = times[SAMPLES / 2];
+    println!(
+        "{:<16} time: [{:.2?} {:.2?} {:.2?}]",
+        name,
+        times[0],
+        median,
+        times[SAMPLES - 1],
+    );
+    (median, result)
+}
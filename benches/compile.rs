@@ -0,0 +1,58 @@
+//! Compares interpreting an expression with calling its compiled form on a sweep of 1M points.
+//!
+//! Run with `cargo bench --bench compile`.
+
+mod common;
+
+use std::time::Duration;
+
+use common::bench;
+use cs220::assignments::assignment04::context::Context;
+use cs220::assignments::assignment04::parser::parse_command;
+
+const POINTS: usize = 1_000_000;
+const SOURCE: &str = "x ^ 3 - 2 * x ^ 2 * y + x / (y + 2) - 3 * y + 1";
+
+/// Prints the running time per point.
+fn per_point(time: Duration) {
+    println!(
+        "{:<16} {:.1} ns/point",
+        "",
+        time.as_nanos() as f64 / POINTS as f64
+    );
+}
+
+fn main() {
+    let expression = parse_command(SOURCE).unwrap().expression;
+    let points = (0..POINTS)
+        .map(|i| {
+            let t = i as f64 / POINTS as f64;
+            (4.0 * t - 2.0, 1.0 - t)
+        })
+        .collect::<Vec<_>>();
+    println!("sweeping `{}` over {} points", SOURCE, POINTS);
+
+    let mut context = Context::new();
+    let (interpreted, expected) = bench("interpreted", || {
+        points
+            .iter()
+            .map(|&(x, y)| {
+                let _ = context.set_var("x", x).set_var("y", y);
+                context.calc_expression(&expression).unwrap()
+            })
+            .sum()
+    });
+    per_point(interpreted);
+
+    let compiled_fn = expression.compile(&["x", "y"]).unwrap();
+    let (compiled, result) = bench("compiled", || {
+        points.iter().map(|&(x, y)| compiled_fn(&[x, y])).sum()
+    });
+    per_point(compiled);
+
+    assert_eq!(result, expected);
+    println!(
+        "speedup: {:.1}x",
+        interpreted.as_secs_f64() / compiled.as_secs_f64()
+    );
+}
@@ -0,0 +1,90 @@
+//! Compares evaluating polynomials of degree 10k by Horner's method with summing their terms, and
+//! multiplying them by the selected algorithm with the schoolbook method.
+//!
+//! Run with `cargo bench --bench polynomial`.
+
+mod common;
+
+use common::bench;
+use cs220::assignments::assignment06::multiplication::schoolbook;
+use cs220::assignments::assignment06::{ModInt, Polynomial, Semiring};
+
+const DEGREE: u64 = 10_000;
+const POINTS: usize = 100;
+
+/// Sums the terms of `poly` at `x`, computing each power separately.
+fn naive(poly: &Polynomial<f64>, x: f64) -> f64 {
+    poly.terms()
+        .map(|(degree, coefficient)| coefficient * x.powi(degree as i32))
+        .sum()
+}
+
+/// Benchmarks both evaluations of `poly` on `points`, and prints the speedup.
+fn compare(name: &str, poly: &Polynomial<f64>, points: &[f64]) {
+    println!("{} at {} points", name, points.len());
+    let (naive_time, expected) = bench("  naive", || points.iter().map(|&x| naive(poly, x)).sum());
+    let (horner_time, result) = bench("  horner", || points.iter().map(|&x| poly.eval(x)).sum());
+    assert!((result - expected).abs() <= 1e-6 * expected.abs().max(1_f64));
+    println!(
+        "  speedup: {:.1}x",
+        naive_time.as_secs_f64() / horner_time.as_secs_f64()
+    );
+}
+
+fn main() {
+    let points = (0..POINTS)
+        .map(|i| -1_f64 + 2_f64 * i as f64 / POINTS as f64)
+        .collect::<Vec<_>>();
+
+    let dense = (0..=DEGREE).fold(Polynomial::zero(), |poly, k| {
+        let coefficient = 1_f64 / (1 + k % 7) as f64;
+        Polynomial::add(&poly, &Polynomial::monomial(coefficient, k))
+    });
+    compare(&format!("dense, degree {}", DEGREE), &dense, &points);
+
+    let sparse = (0..=DEGREE)
+        .step_by(1000)
+        .fold(Polynomial::zero(), |poly, k| {
+            Polynomial::add(&poly, &Polynomial::monomial(1_f64, k))
+        });
+    compare(&format!("sparse, degree {}", DEGREE), &sparse, &points);
+
+    println!("product, degree {}", DEGREE);
+    let coefficients = (0..=DEGREE / 2).map(|k| k % 7).collect::<Vec<_>>();
+    let integers = coefficients.iter().map(|&k| k as i64).collect::<Vec<_>>();
+    let residues = coefficients
+        .iter()
+        .map(|&k| ModInt::<998_244_353>::new(k))
+        .collect::<Vec<_>>();
+    let floats = coefficients.iter().map(|&k| k as f64).collect::<Vec<_>>();
+    let (schoolbook_time, expected) = bench("  schoolbook", || {
+        schoolbook(&integers, &integers).iter().sum::<i64>() as f64
+    });
+    let products: [(&str, &dyn Fn() -> f64); 3] = [
+        ("  karatsuba", &|| {
+            i64::mul_coefficients(&integers, &integers)
+                .iter()
+                .sum::<i64>() as f64
+        }),
+        ("  ntt", &|| {
+            ModInt::mul_coefficients(&residues, &residues)
+                .iter()
+                .map(|residue| residue.value())
+                .sum::<u64>() as f64
+        }),
+        ("  fft", &|| {
+            f64::mul_coefficients(&floats, &floats)
+                .iter()
+                .map(|value| value.round())
+                .sum()
+        }),
+    ];
+    for (name, product) in products {
+        let (time, result) = bench(name, product);
+        assert_eq!(result, expected);
+        println!(
+            "  speedup: {:.1}x",
+            schoolbook_time.as_secs_f64() / time.as_secs_f64()
+        );
+    }
+}
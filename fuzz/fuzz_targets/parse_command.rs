@@ -0,0 +1,13 @@
+//! The parser never panics, whatever UTF-8 text it is given.
+//!
+//! Run with `cargo +nightly fuzz run parse_command fuzz/corpus/parse_command fuzz/seeds/parse_command`,
+//! which also starts from the deeply nested inputs in `fuzz/seeds`.
+
+#![no_main]
+
+use cs220::assignments::assignment04::parser::parse_command;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|line: &str| {
+    let _ = parse_command(line);
+});
@@ -0,0 +1,16 @@
+//! Printing an arbitrary command and parsing it back yields the same command.
+//!
+//! Run with `cargo +nightly fuzz run round_trip`.
+
+#![no_main]
+
+use cs220::assignments::assignment04::arbitrary::Gen;
+use cs220::assignments::assignment04::parser::parse_command;
+use cs220::assignments::assignment04::syntax::Command;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    let command = Gen::from_bytes(bytes).arbitrary::<Command>();
+    let line = command.to_string();
+    assert_eq!(parse_command(&line).unwrap(), command, "{}", line);
+});
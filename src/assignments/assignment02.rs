@@ -43,10 +43,19 @@ pub fn up3(n: u64) -> u64 {
 
 /// Returns the greatest common divisor (GCD) of two non-negative integers. (We assume the absence of integer overflow.)
 pub fn gcd(lhs: u64, rhs: u64) -> u64 {
+    extended_gcd(lhs, rhs).0
+}
+
+/// Returns the GCD `g` of two non-negative integers together with Bézout coefficients `x` and `y`,
+/// i.e., `lhs * x + rhs * y = g`.
+///
+/// Consult <https://en.wikipedia.org/wiki/Extended_Euclidean_algorithm>.
+pub fn extended_gcd(lhs: u64, rhs: u64) -> (u64, i128, i128) {
     if rhs == 0 {
-        return lhs;
+        return (lhs, 1, 0);
     }
-    gcd(rhs, lhs % rhs)
+    let (g, x, y) = extended_gcd(rhs, lhs % rhs);
+    (g, y, x - (lhs / rhs) as i128 * y)
 }
 
 /// Returns the array of nC0, nC1, nC2, ..., nCn, where nCk = n! / (k! * (n-k)!). (We assume the absence of integer overflow.)
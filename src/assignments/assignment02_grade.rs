@@ -39,6 +39,21 @@ mod test {
         assert_eq!(gcd(0, 33), 33);
     }
 
+    #[test]
+    fn test_extended_gcd() {
+        for (lhs, rhs, g) in [
+            (240, 46, 2),
+            (20, 63, 1),
+            (0, 33, 33),
+            (33, 0, 33),
+            (u64::MAX, 3, 3),
+        ] {
+            let (gcd, x, y) = extended_gcd(lhs, rhs);
+            assert_eq!(gcd, g);
+            assert_eq!(lhs as i128 * x + rhs as i128 * y, g as i128);
+        }
+    }
+
     #[test]
     fn test_sum_array() {
         assert_eq!(sum_array(&[]), 0);
@@ -6,6 +6,7 @@
 //! You should fill out the `todo!()` placeholders in such a way that `/scripts/grade-03.sh` works fine.
 //! See `assignment03_grade.rs` and `/scripts/grade-03.sh` for the test script.
 
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 
 /// Day of week.
@@ -119,40 +120,62 @@ pub fn my_and_then<T, U, F: FnOnce(T) -> MyOption<U>>(v: MyOption<T>, f: F) -> M
 ///
 /// Returns `None` if the list is empty.
 pub fn median(values: Vec<isize>) -> Option<isize> {
-    let mut value = vec![];
-    for i in values {
-        value.push(i)
-    }
-    value.sort();
-    if value.is_empty() {
+    middle_by(values, isize::cmp).map(|(_, upper)| upper)
+}
+
+/// Given a list of values and how to compare them, returns the values in the middle positions
+/// when sorted: the `n/2`-th and `(n/2)+1`-th smallest values if `n` is even, and the
+/// `(n+1)/2`-th smallest value twice if `n` is odd.
+///
+/// This generalizes [`median`] to values without a total order, e.g. `f64` compared with
+/// `f64::total_cmp`.
+///
+/// Returns `None` if the list is empty.
+pub fn middle_by<T: Clone, F: FnMut(&T, &T) -> Ordering>(
+    mut values: Vec<T>,
+    compare: F,
+) -> Option<(T, T)> {
+    if values.is_empty() {
         return None;
     }
-    if value.len() % 2 == 0 {
-        Some(value[(value.len() + 1) / 2])
-    } else {
-        Some(value[value.len() / 2])
-    }
+    values.sort_by(compare);
+    let upper = values.len() / 2;
+    let lower = (values.len() - 1) / 2;
+    Some((values[lower].clone(), values[upper].clone()))
 }
 
 /// Given a list of integers, returns its smallest mode (the value that occurs most often; a hash map will be helpful here).
 ///
 /// Returns `None` if the list is empty.
 pub fn mode(values: Vec<isize>) -> Option<isize> {
-    if values.is_empty() {
-        return None;
-    }
-    let mut num_map = HashMap::new();
-    let mut max_num = 0;
-    let mut max_mode = 0;
-    for i in values {
-        let entry = num_map.entry(i).or_insert(0);
-        *entry += 1;
-        if *entry > max_num {
-            max_num = *entry;
-            max_mode = i;
+    mode_by(values, isize::cmp)
+}
+
+/// Given a list of values and how to compare them, returns the smallest mode.
+///
+/// This generalizes [`mode`] to values that cannot be hashed, e.g. `f64` compared with
+/// `f64::total_cmp`: equal values are found next to each other after sorting.
+///
+/// Returns `None` if the list is empty.
+pub fn mode_by<T, F: FnMut(&T, &T) -> Ordering>(mut values: Vec<T>, mut compare: F) -> Option<T> {
+    values.sort_by(&mut compare);
+    let mut mode = None;
+    let mut max_count = 0;
+    let mut values = values.into_iter().peekable();
+    while let Some(value) = values.next() {
+        let mut count = 1;
+        while values
+            .next_if(|next| compare(&value, next) == Ordering::Equal)
+            .is_some()
+        {
+            count += 1;
+        }
+        if count > max_count {
+            max_count = count;
+            mode = Some(value);
         }
     }
-    Some(max_mode)
+    mode
 }
 
 /// Converts the given string to Pig Latin. Use the rules below to translate normal English into Pig Latin.
@@ -81,6 +81,26 @@ mod test {
         assert_eq!(mode(vec![1, 1, 2, 2, 3, 3]), Some(1));
     }
 
+    #[test]
+    fn test_generic_statistics() {
+        assert_eq!(middle_by(Vec::<f64>::new(), f64::total_cmp), None);
+        assert_eq!(middle_by(vec![2.5], f64::total_cmp), Some((2.5, 2.5)));
+        assert_eq!(
+            middle_by(vec![0.5, -1.0, 3.0, 2.0], f64::total_cmp),
+            Some((0.5, 2.0))
+        );
+        assert_eq!(
+            middle_by(vec![0.5, -1.0, 3.0], f64::total_cmp),
+            Some((0.5, 0.5))
+        );
+
+        assert_eq!(mode_by(Vec::<f64>::new(), f64::total_cmp), None);
+        assert_eq!(mode_by(vec![0.1, 0.2, 0.2, 0.1], f64::total_cmp), Some(0.1));
+        assert_eq!(mode_by(vec![2.0, 2.0, 1.0, 1.0], f64::total_cmp), Some(1.0));
+        assert_eq!(mode_by(vec![3.5, 1.5, 3.5], f64::total_cmp), Some(3.5));
+        assert_eq!(mode(vec![2, 2, 1, 1]), Some(1));
+    }
+
     #[test]
     fn test_piglatin() {
         assert_eq!(piglatin("pig".to_string()), "igpay".to_string());
@@ -0,0 +1,190 @@
+//! Random syntax trees for property tests and fuzzing.
+//!
+//! [`Arbitrary`] builds a value from the choices made by a [`Gen`], which is either a seeded
+//! pseudorandom generator (for property tests) or the bytes given by a fuzzer. Generated
+//! expressions are those the parser can produce: printing one and parsing it back yields the same
+//! tree.
+
+use super::syntax::{BinOp, Command, Expression, UnOp};
+
+/// Names of generated variables, and of the parameters of generated lambdas.
+const VARIABLES: [&str; 6] = ["x", "y", "z", "a1", "rate_2", "$0"];
+
+/// Names of generated calls: built-ins, statistics, and an unknown function.
+const FUNCTIONS: [&str; 8] = [
+    "range",
+    "len",
+    "sum",
+    "map",
+    "reduce",
+    "mean",
+    "percentile",
+    "f",
+];
+
+/// Source of the choices made by [`Arbitrary`] implementations.
+#[derive(Debug, Clone)]
+pub struct Gen<'b> {
+    source: Source<'b>,
+    depth: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Source<'b> {
+    /// SplitMix64 state.
+    Seed(u64),
+    /// Remaining bytes. Once they run out, every choice is the first option.
+    Bytes(&'b [u8]),
+}
+
+impl Gen<'static> {
+    /// Creates a pseudorandom generator from the seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            source: Source::Seed(seed),
+            depth: 6,
+        }
+    }
+}
+
+impl<'b> Gen<'b> {
+    /// Creates a generator that takes its choices from the bytes, e.g. those given by a fuzzer.
+    pub fn from_bytes(bytes: &'b [u8]) -> Self {
+        Self {
+            source: Source::Bytes(bytes),
+            depth: 6,
+        }
+    }
+
+    /// Sets the maximum nesting depth of generated expressions. The default is 6.
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match &mut self.source {
+            Source::Seed(state) => {
+                *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                let mut z = *state;
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                z ^ (z >> 31)
+            }
+            Source::Bytes(bytes) => {
+                let (head, tail) = bytes.split_at(bytes.len().min(8));
+                *bytes = tail;
+                head.iter().fold(0, |z, byte| z << 8 | u64::from(*byte))
+            }
+        }
+    }
+
+    /// Returns a number below `n`, which must be positive.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns one of the items, which must not be empty.
+    pub fn choose<'i, T>(&mut self, items: &'i [T]) -> &'i T {
+        &items[self.below(items.len())]
+    }
+
+    /// Returns an arbitrary value.
+    pub fn arbitrary<T: Arbitrary>(&mut self) -> T {
+        T::arbitrary(self)
+    }
+
+    /// Returns up to `max` arbitrary values.
+    fn some<T: Arbitrary>(&mut self, max: usize) -> Vec<T> {
+        (0..self.below(max + 1)).map(|_| self.arbitrary()).collect()
+    }
+
+    /// Returns the value generated by `f` one level deeper.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.depth -= 1;
+        let value = f(self);
+        self.depth += 1;
+        value
+    }
+}
+
+/// Value that can be generated from a [`Gen`].
+pub trait Arbitrary: Sized {
+    /// Generates a value.
+    fn arbitrary(gen: &mut Gen<'_>) -> Self;
+}
+
+impl Arbitrary for BinOp {
+    fn arbitrary(gen: &mut Gen<'_>) -> Self {
+        *gen.choose(&[
+            BinOp::Add,
+            BinOp::Subtract,
+            BinOp::Multiply,
+            BinOp::Divide,
+            BinOp::Power,
+            BinOp::PlusMinus,
+        ])
+    }
+}
+
+impl Arbitrary for UnOp {
+    fn arbitrary(gen: &mut Gen<'_>) -> Self {
+        *gen.choose(&[UnOp::Negate, UnOp::Factorial, UnOp::Percent])
+    }
+}
+
+/// Generates a non-negative number: mostly small integers, sometimes fractions or huge numbers.
+fn number(gen: &mut Gen<'_>) -> f64 {
+    match gen.below(8) {
+        0 => gen.below(64) as f64 / 8_f64,
+        1 => 10_f64.powi(gen.below(40) as i32 * 10 - 100),
+        _ => gen.below(13) as f64,
+    }
+}
+
+fn variable(gen: &mut Gen<'_>) -> String {
+    gen.choose(&VARIABLES).to_string()
+}
+
+impl Arbitrary for Expression {
+    fn arbitrary(gen: &mut Gen<'_>) -> Self {
+        // Leaves are more likely to be chosen (and are always chosen at the maximum depth), so
+        // that trees stay small.
+        let choice = if gen.depth == 0 { 0 } else { gen.below(12) };
+        match choice {
+            0..=2 => Expression::Num(number(gen)),
+            3..=4 => Expression::Variable(variable(gen)),
+            5..=7 => gen.nested(|gen| Expression::BinOp {
+                op: gen.arbitrary(),
+                lhs: Box::new(gen.arbitrary()),
+                rhs: Box::new(gen.arbitrary()),
+            }),
+            8 => gen.nested(|gen| Expression::UnOp {
+                op: gen.arbitrary(),
+                expr: Box::new(gen.arbitrary()),
+            }),
+            9 => gen.nested(|gen| Expression::Call {
+                name: gen.choose(&FUNCTIONS).to_string(),
+                args: gen.some(3),
+            }),
+            10 => gen.nested(|gen| Expression::List(gen.some(3))),
+            _ => gen.nested(|gen| Expression::Lambda {
+                params: (0..gen.below(3)).map(|_| variable(gen)).collect(),
+                body: Box::new(gen.arbitrary()),
+            }),
+        }
+    }
+}
+
+impl Arbitrary for Command {
+    fn arbitrary(gen: &mut Gen<'_>) -> Self {
+        Command {
+            variable: if gen.below(4) == 0 {
+                Some(variable(gen))
+            } else {
+                None
+            },
+            expression: gen.arbitrary(),
+        }
+    }
+}
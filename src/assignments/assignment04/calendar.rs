@@ -0,0 +1,394 @@
+//! Dates, times of day, and durations.
+//!
+//! Dates follow the proleptic Gregorian calendar from year 0 to 9999, without time zones or leap
+//! seconds. `calc` reads `2026-10-18` as `date(2026, 10, 18)`, `2026-10-18T09:30` as
+//! `datetime(2026, 10, 18, 9, 30, 0)`, and `3 weeks` as `weeks(3)`, so that
+//! `2026-10-18 + 3 weeks` is a date.
+
+use std::fmt;
+use std::ops;
+use std::result::Result::Ok;
+
+use anyhow::*;
+
+use super::context::{Context, Dynamic};
+use crate::assignments::assignment03::{next_weekday, DayOfWeek};
+
+const SECONDS_PER_MINUTE: i64 = 60;
+const SECONDS_PER_HOUR: i64 = 60 * SECONDS_PER_MINUTE;
+const SECONDS_PER_DAY: i64 = 24 * SECONDS_PER_HOUR;
+const SECONDS_PER_WEEK: i64 = 7 * SECONDS_PER_DAY;
+
+/// Units of durations, from the largest, with their lengths in seconds.
+pub const UNITS: [(&str, i64); 5] = [
+    ("weeks", SECONDS_PER_WEEK),
+    ("days", SECONDS_PER_DAY),
+    ("hours", SECONDS_PER_HOUR),
+    ("minutes", SECONDS_PER_MINUTE),
+    ("seconds", 1),
+];
+
+/// Returns the number of days from 1970-01-01 to the date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Years start in March, so that the leap day is the last day of a year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Returns the year, month, and day of the date `days` days after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Returns the number of days of the month.
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Date, possibly with a time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// Seconds since 1970-01-01T00:00:00.
+    seconds: i64,
+}
+
+/// Signed length of time, in whole seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration {
+    seconds: i64,
+}
+
+impl Date {
+    /// Creates the date at midnight.
+    pub fn new(year: i64, month: u32, day: u32) -> Result<Self> {
+        if !(0..=9999).contains(&year) {
+            bail!("year {} is not between 0 and 9999", year);
+        }
+        if !(1..=12).contains(&month) {
+            bail!("month {} is not between 1 and 12", month);
+        }
+        if day == 0 || day > days_in_month(year, month) {
+            bail!("{:04}-{:02} has no day {}", year, month, day);
+        }
+        Ok(Self {
+            seconds: days_from_civil(year, month, day) * SECONDS_PER_DAY,
+        })
+    }
+
+    /// Returns the date at the time of day.
+    pub fn at(self, hour: u32, minute: u32, second: u32) -> Result<Self> {
+        if hour >= 24 || minute >= 60 || second >= 60 {
+            bail!(
+                "{:02}:{:02}:{:02} is not a time of day",
+                hour,
+                minute,
+                second
+            );
+        }
+        Ok(Self {
+            seconds: self.days() * SECONDS_PER_DAY
+                + hour as i64 * SECONDS_PER_HOUR
+                + minute as i64 * SECONDS_PER_MINUTE
+                + second as i64,
+        })
+    }
+
+    /// Returns the number of days since 1970-01-01.
+    pub fn days(&self) -> i64 {
+        self.seconds.div_euclid(SECONDS_PER_DAY)
+    }
+
+    /// Returns the year, month, and day.
+    pub fn ymd(&self) -> (i64, u32, u32) {
+        civil_from_days(self.days())
+    }
+
+    /// Returns the hour, minute, and second of the time of day.
+    pub fn hms(&self) -> (u32, u32, u32) {
+        let seconds = self.seconds.rem_euclid(SECONDS_PER_DAY);
+        (
+            (seconds / SECONDS_PER_HOUR) as u32,
+            (seconds % SECONDS_PER_HOUR / SECONDS_PER_MINUTE) as u32,
+            (seconds % SECONDS_PER_MINUTE) as u32,
+        )
+    }
+
+    /// Returns the day of week.
+    pub fn weekday(&self) -> DayOfWeek {
+        // 1970-01-01 was a Thursday.
+        match (self.days() + 4).rem_euclid(7) {
+            0 => DayOfWeek::Sun,
+            1 => DayOfWeek::Mon,
+            2 => DayOfWeek::Tue,
+            3 => DayOfWeek::Wed,
+            4 => DayOfWeek::Thu,
+            5 => DayOfWeek::Fri,
+            _ => DayOfWeek::Sat,
+        }
+    }
+
+    /// Returns the date after the duration, which fails past year 9999 or before year 0.
+    pub fn after(self, duration: Duration) -> Result<Self> {
+        let date = self
+            .seconds
+            .checked_add(duration.seconds)
+            .map(|seconds| Self { seconds });
+        match date {
+            Some(date) if (0..=9999).contains(&date.ymd().0) => Ok(date),
+            _ => bail!("{} + {} is not between year 0 and 9999", self, duration),
+        }
+    }
+
+    /// Returns the duration from `other` to the date.
+    pub fn since(self, other: Self) -> Duration {
+        Duration {
+            seconds: self.seconds - other.seconds,
+        }
+    }
+
+    /// Returns the number of days from the date to `other`, ignoring times of day.
+    pub fn days_between(self, other: Self) -> i64 {
+        other.days() - self.days()
+    }
+
+    /// Returns the date `count` business days later, at the same time of day.
+    ///
+    /// Each business day is the [`next_weekday`], so weekends are skipped, and a day on a weekend
+    /// moves to Monday.
+    pub fn add_business_days(self, count: u64) -> Result<Self> {
+        let mut date = self;
+        let mut count = count;
+        // From a weekday, five business days are a week.
+        while count > 0 && (count % 5 != 0 || !is_weekday(date.weekday())) {
+            let (day, next) = (date.weekday(), next_weekday(date.weekday()));
+            let days = (next as i64 - day as i64).rem_euclid(7);
+            date = date.after(Duration::from_days(days))?;
+            count -= 1;
+        }
+        match i64::try_from(count / 5) {
+            Ok(weeks) if weeks <= i64::MAX / SECONDS_PER_WEEK => date.after(Duration {
+                seconds: weeks * SECONDS_PER_WEEK,
+            }),
+            _ => bail!("{} business days after {} is after year 9999", count, self),
+        }
+    }
+
+    /// Returns the number of weekdays after the date up to `other` inclusive, which is negative if
+    /// `other` is earlier.
+    pub fn business_days_between(self, other: Self) -> i64 {
+        if other < self {
+            return -other.business_days_between(self);
+        }
+        let days = self.days_between(other);
+        let (weeks, rest) = (days / 7, days % 7);
+        let rest = (1..=rest)
+            .filter(|i| is_weekday(Self::from_days(self.days() + i).weekday()))
+            .count();
+        weeks * 5 + rest as i64
+    }
+
+    fn from_days(days: i64) -> Self {
+        Self {
+            seconds: days * SECONDS_PER_DAY,
+        }
+    }
+}
+
+/// Returns whether the day is from Monday to Friday.
+fn is_weekday(day: DayOfWeek) -> bool {
+    !matches!(day, DayOfWeek::Sat | DayOfWeek::Sun)
+}
+
+/// Prints `2026-10-18`, or `2026-10-18T09:30:00` with the time of day.
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)?;
+        match self.hms() {
+            (0, 0, 0) => Ok(()),
+            (hour, minute, second) => write!(f, "T{:02}:{:02}:{:02}", hour, minute, second),
+        }
+    }
+}
+
+impl Duration {
+    /// Creates the duration of `count` units of `unit` seconds, rounded to whole seconds.
+    pub fn new(count: f64, unit: i64) -> Result<Self> {
+        let seconds = (count * unit as f64).round();
+        // Far longer than any duration between dates, and far from overflowing when added.
+        if !seconds.is_finite() || seconds.abs() > 1e12 * SECONDS_PER_DAY as f64 {
+            bail!("{} is not a duration", count);
+        }
+        Ok(Self {
+            seconds: seconds as i64,
+        })
+    }
+
+    fn from_days(days: i64) -> Self {
+        Self {
+            seconds: days * SECONDS_PER_DAY,
+        }
+    }
+
+    /// Returns the length in seconds.
+    pub fn seconds(&self) -> i64 {
+        self.seconds
+    }
+
+    /// Returns the sum, unless it overflows.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.seconds
+            .checked_add(other.seconds)
+            .map(|seconds| Self { seconds })
+    }
+
+    /// Returns the duration scaled by `factor`.
+    pub fn scale(self, factor: f64) -> Result<Self> {
+        Self::new(factor, self.seconds)
+    }
+}
+
+impl ops::Neg for Duration {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            seconds: -self.seconds,
+        }
+    }
+}
+
+/// Prints the weeks, days, hours, minutes, and seconds that make up the duration, e.g.
+/// `1 week 2 days 3 hours`. Each is negative if the duration is.
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.seconds == 0 {
+            return write!(f, "0 seconds");
+        }
+        let sign = if self.seconds < 0 { "-" } else { "" };
+        let mut rest = self.seconds.unsigned_abs();
+        let mut first = true;
+        for (unit, length) in UNITS {
+            let count = rest / length as u64;
+            rest %= length as u64;
+            if count == 0 {
+                continue;
+            }
+            if !first {
+                write!(f, " ")?;
+            }
+            first = false;
+            let unit = if count == 1 {
+                &unit[..unit.len() - 1]
+            } else {
+                unit
+            };
+            write!(f, "{}{} {}", sign, count, unit)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the argument of the function as a date.
+fn date(name: &str, arg: &Dynamic) -> Result<Date> {
+    match arg {
+        Dynamic::Date(date) => Ok(*date),
+        _ => bail!("{} expects a date, but got {}", name, arg),
+    }
+}
+
+/// Returns the argument of the function as a non-negative integer.
+fn natural(name: &str, arg: &Dynamic) -> Result<u64> {
+    match arg {
+        Dynamic::Num(num) if num.fract() == 0_f64 && *num >= 0_f64 && *num < 2_f64.powi(64) => {
+            Ok(*num as u64)
+        }
+        _ => bail!("{} expects a non-negative integer, but got {}", name, arg),
+    }
+}
+
+/// Returns the date given by the year, month, and day arguments of the function.
+fn civil_date(name: &str, args: &[Dynamic]) -> Result<Date> {
+    let year = number(name, &args[0])?;
+    if year.fract() != 0_f64 || year.abs() > 1e9 {
+        bail!("year {} is not an integer", year);
+    }
+    let (month, day) = (natural(name, &args[1])?, natural(name, &args[2])?);
+    Date::new(year as i64, month.min(13) as u32, day.min(32) as u32)
+}
+
+/// Returns the argument of the function as a number.
+fn number(name: &str, arg: &Dynamic) -> Result<f64> {
+    match arg {
+        Dynamic::Num(num) => Ok(*num),
+        _ => bail!("{} expects a number, but got {}", name, arg),
+    }
+}
+
+/// Registers:
+///
+/// - `date(year, month, day)` and `datetime(year, month, day, hour, minute, second)`;
+/// - `weeks(n)`, `days(n)`, `hours(n)`, `minutes(n)`, and `seconds(n)`, the durations;
+/// - `weekday(date)`, the day of week;
+/// - `days_between(a, b)`, the number of days from `a` to `b`;
+/// - `add_business_days(date, n)` and `business_days_between(a, b)`, which skip weekends.
+pub fn register(context: &mut Context<Dynamic>) -> &mut Context<Dynamic> {
+    let _ = context.register_fn("date", 3, |args| {
+        Ok(Dynamic::Date(civil_date("date", args)?))
+    });
+    let _ = context.register_fn("datetime", 6, |args| {
+        let date = civil_date("datetime", args)?;
+        let time = args[3..]
+            .iter()
+            .map(|arg| natural("datetime", arg).map(|field| field.min(60) as u32))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Dynamic::Date(date.at(time[0], time[1], time[2])?))
+    });
+    for (unit, length) in UNITS {
+        let _ = context.register_fn(unit, 1, move |args| {
+            Ok(Dynamic::Duration(Duration::new(
+                number(unit, &args[0])?,
+                length,
+            )?))
+        });
+    }
+    let _ = context.register_fn("weekday", 1, |args| {
+        Ok(Dynamic::Day(date("weekday", &args[0])?.weekday()))
+    });
+    let _ = context.register_fn("days_between", 2, |args| {
+        let (a, b) = (
+            date("days_between", &args[0])?,
+            date("days_between", &args[1])?,
+        );
+        Ok(Dynamic::Num(a.days_between(b) as f64))
+    });
+    let _ = context.register_fn("add_business_days", 2, |args| {
+        let date = date("add_business_days", &args[0])?;
+        let count = natural("add_business_days", &args[1])?;
+        Ok(Dynamic::Date(date.add_business_days(count)?))
+    });
+    context.register_fn("business_days_between", 2, |args| {
+        let a = date("business_days_between", &args[0])?;
+        let b = date("business_days_between", &args[1])?;
+        Ok(Dynamic::Num(a.business_days_between(b) as f64))
+    })
+}
@@ -0,0 +1,87 @@
+//! Compiling expressions into closures.
+//!
+//! [`Context::calc_expression`](super::context::Context::calc_expression) walks the syntax tree
+//! and looks up every variable in a `HashMap` each time it is evaluated. When the same expression
+//! is evaluated at many points, e.g. when plotting it, [`Expression::compile`] pays that cost once:
+//! it turns the tree into a tree of closures in which operators are already dispatched and
+//! variables are indices into the argument slice.
+
+use anyhow::*;
+
+use super::context::Value;
+use super::syntax::{BinOp, Expression, UnOp};
+
+/// Compiled expression, taking the values of the variables in order.
+type Compiled = Box<dyn Fn(&[f64]) -> f64 + Send + Sync>;
+
+impl Expression {
+    /// Compiles the expression into a function of the given variables, in order.
+    ///
+    /// The function agrees with evaluating the expression in an `f64` context where the variables
+    /// are set to the arguments, except that where evaluation fails (dividing by zero, or the
+    /// factorial of a non-natural number) it returns NaN. It panics if it is given fewer arguments
+    /// than there are variables.
+    ///
+    /// Returns an error if the expression refers to any other variable, or contains a function
+    /// call, a list, a lambda, or an uncertainty.
+    pub fn compile(&self, vars: &[&str]) -> Result<impl Fn(&[f64]) -> f64 + Send + Sync> {
+        compile(self, vars)
+    }
+}
+
+fn compile(expression: &Expression, vars: &[&str]) -> Result<Compiled> {
+    Ok(match expression {
+        Expression::Num(num) => {
+            let num = *num;
+            Box::new(move |_| num)
+        }
+        Expression::Variable(var) => match vars.iter().position(|name| name == var) {
+            Some(index) => Box::new(move |args| args[index]),
+            None => bail!("variable {} is not an argument", var),
+        },
+        Expression::UnOp { op, expr } => {
+            let expr = compile(expr, vars)?;
+            match op {
+                UnOp::Negate => Box::new(move |args| -expr(args)),
+                UnOp::Factorial => Box::new(move |args| {
+                    f64::calc_unop(UnOp::Factorial, &expr(args)).unwrap_or(f64::NAN)
+                }),
+                UnOp::Percent => Box::new(move |args| expr(args) / 100_f64),
+            }
+        }
+        Expression::BinOp { op, lhs, rhs } if rhs.is_percent_of(*op) => {
+            let (lhs, rhs) = (compile(lhs, vars)?, compile(rhs, vars)?);
+            let add = *op == BinOp::Add;
+            Box::new(move |args| {
+                let lhs = lhs(args);
+                let rhs = lhs * rhs(args);
+                if add {
+                    lhs + rhs
+                } else {
+                    lhs - rhs
+                }
+            })
+        }
+        Expression::BinOp { op, lhs, rhs } => {
+            let (lhs, rhs) = (compile(lhs, vars)?, compile(rhs, vars)?);
+            match op {
+                BinOp::Add => Box::new(move |args| lhs(args) + rhs(args)),
+                BinOp::Subtract => Box::new(move |args| lhs(args) - rhs(args)),
+                BinOp::Multiply => Box::new(move |args| lhs(args) * rhs(args)),
+                BinOp::Divide => Box::new(move |args| {
+                    let rhs = rhs(args);
+                    if rhs == 0_f64 {
+                        f64::NAN
+                    } else {
+                        lhs(args) / rhs
+                    }
+                }),
+                BinOp::Power => Box::new(move |args| lhs(args).powf(rhs(args))),
+                BinOp::PlusMinus => bail!("cannot compile an uncertainty"),
+            }
+        }
+        Expression::Call { name, .. } => bail!("cannot compile a call to {}", name),
+        Expression::List(_) => bail!("cannot compile a list"),
+        Expression::Lambda { .. } => bail!("cannot compile a lambda"),
+    })
+}
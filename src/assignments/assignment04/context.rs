@@ -1,13 +1,18 @@
 //! Calculator.
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::slice;
+use std::sync::Arc;
 
 use anyhow::*;
 
-use super::syntax::{BinOp, Command, Expression};
+use super::calendar::{Date, Duration};
+use super::limits::{Budget, Limits};
+use super::syntax::{fmt_params, BinOp, Command, Expression, UnOp};
+use crate::assignments::assignment03::DayOfWeek;
 
 use std::error::Error;
-use std::fmt;
+use std::fmt::{self, Debug};
 use std::result::Result::Ok;
 
 #[derive(Debug)]
@@ -35,11 +40,476 @@ impl Error for MyError {
     }
 }
 
+/// Values the calculator computes with.
+///
+/// `f64` is the default; other implementations (e.g. [`Uncertain`](super::uncertain::Uncertain))
+/// change how literals and operators are interpreted.
+pub trait Value: Debug + Clone + fmt::Display {
+    /// Converts a number literal.
+    fn from_num(num: f64) -> Self;
+    /// Applies the unary operator.
+    fn calc_unop(op: UnOp, operand: &Self) -> Result<Self>;
+    /// Applies the binary operator.
+    fn calc_binop(op: BinOp, lhs: &Self, rhs: &Self) -> Result<Self>;
+    /// Approximates the value as a number, if it is one. Used for enforcing
+    /// [`Limits::max_integer_bits`].
+    fn as_f64(&self) -> Option<f64> {
+        None
+    }
+    /// Wraps the function created by a lambda expression, if the value type has functions.
+    fn from_closure(_closure: Closure<Self>) -> Option<Self> {
+        None
+    }
+    /// Returns the function, if the value is one.
+    fn as_closure(&self) -> Option<&Closure<Self>> {
+        None
+    }
+    /// Wraps the list, if the value type has lists.
+    fn from_list(_list: Vec<Self>) -> Option<Self> {
+        None
+    }
+    /// Returns the list's elements, if the value is a list.
+    fn as_list(&self) -> Option<&[Self]> {
+        None
+    }
+    /// Describes anything worth reporting that happened while calculating the value, e.g. an
+    /// integer overflow.
+    fn warning(&self) -> Option<String> {
+        None
+    }
+}
+
+impl Value for f64 {
+    fn from_num(num: f64) -> Self {
+        num
+    }
+
+    fn calc_unop(op: UnOp, operand: &Self) -> Result<Self> {
+        match op {
+            UnOp::Negate => Ok(-operand),
+            UnOp::Factorial => {
+                if *operand < 0_f64 || operand.fract() != 0_f64 {
+                    Err(anyhow::Error::new(MyError::new(
+                        "factorial of a non-natural number",
+                    )))
+                } else if *operand > 170_f64 {
+                    // Overflows `f64` anyway.
+                    Ok(f64::INFINITY)
+                } else {
+                    Ok((1..=*operand as u64).map(|i| i as f64).product())
+                }
+            }
+            UnOp::Percent => Ok(operand / 100_f64),
+        }
+    }
+
+    fn calc_binop(op: BinOp, lhs: &Self, rhs: &Self) -> Result<Self> {
+        match op {
+            BinOp::Add => Ok(lhs + rhs),
+            BinOp::Subtract => Ok(lhs - rhs),
+            BinOp::Multiply => Ok(lhs * rhs),
+            BinOp::Divide => {
+                if *rhs == 0_f64 {
+                    Err(anyhow::Error::new(MyError::new("cannot divide 0")))
+                } else {
+                    Ok(lhs / rhs)
+                }
+            }
+            BinOp::Power => Ok(lhs.powf(*rhs)),
+            BinOp::PlusMinus => Err(anyhow::Error::new(MyError::new(
+                "uncertainties need an uncertain value type",
+            ))),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        Some(*self)
+    }
+}
+
+/// Function created by a lambda expression, together with the variables it captured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure<V> {
+    params: Vec<String>,
+    body: Arc<Expression>,
+    /// Variables the body refers to that were bound when the lambda was evaluated.
+    captured: Arc<HashMap<String, V>>,
+}
+
+impl<V> Closure<V> {
+    /// Returns the parameters.
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+
+    /// Returns the body.
+    pub fn body(&self) -> &Expression {
+        &self.body
+    }
+}
+
+impl<V> fmt::Display for Closure<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_params(f, &self.params)?;
+        write!(f, " -> {}", self.body)
+    }
+}
+
+/// Value that is a number, a list, a function, or a date. `calc` computes with it, so that lambdas
+/// and the list built-ins (`map`, `filter`, `reduce`, `range`, `len`, and `sum`) are available.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Dynamic {
+    /// Number.
+    Num(f64),
+    /// Date, possibly with a time of day.
+    Date(Date),
+    /// Duration.
+    Duration(Duration),
+    /// Day of week.
+    Day(DayOfWeek),
+    /// List.
+    List(Vec<Dynamic>),
+    /// Function.
+    Function(Closure<Dynamic>),
+}
+
+impl From<f64> for Dynamic {
+    fn from(num: f64) -> Self {
+        Dynamic::Num(num)
+    }
+}
+
+impl Value for Dynamic {
+    fn from_num(num: f64) -> Self {
+        Dynamic::Num(num)
+    }
+
+    fn calc_unop(op: UnOp, operand: &Self) -> Result<Self> {
+        match operand {
+            Dynamic::Num(num) => f64::calc_unop(op, num).map(Dynamic::Num),
+            Dynamic::Duration(duration) if op == UnOp::Negate => Ok(Dynamic::Duration(-*duration)),
+            _ => Err(anyhow::Error::new(MyError::new(&format!(
+                "`{}` expects a number",
+                op.symbol()
+            )))),
+        }
+    }
+
+    fn calc_binop(op: BinOp, lhs: &Self, rhs: &Self) -> Result<Self> {
+        match (lhs, rhs) {
+            (Dynamic::Num(lhs), Dynamic::Num(rhs)) => {
+                f64::calc_binop(op, lhs, rhs).map(Dynamic::Num)
+            }
+            (Dynamic::Date(date), Dynamic::Duration(duration))
+            | (Dynamic::Duration(duration), Dynamic::Date(date))
+                if op == BinOp::Add =>
+            {
+                date.after(*duration).map(Dynamic::Date)
+            }
+            (Dynamic::Date(date), Dynamic::Duration(duration)) if op == BinOp::Subtract => {
+                date.after(-*duration).map(Dynamic::Date)
+            }
+            (Dynamic::Date(lhs), Dynamic::Date(rhs)) if op == BinOp::Subtract => {
+                Ok(Dynamic::Duration(lhs.since(*rhs)))
+            }
+            (Dynamic::Duration(lhs), Dynamic::Duration(rhs)) => match op {
+                BinOp::Add => lhs.checked_add(*rhs),
+                BinOp::Subtract => lhs.checked_add(-*rhs),
+                BinOp::Divide => {
+                    return f64::calc_binop(op, &(lhs.seconds() as f64), &(rhs.seconds() as f64))
+                        .map(Dynamic::Num)
+                }
+                _ => bail!("`{}` of durations", op.symbol()),
+            }
+            .map(Dynamic::Duration)
+            .ok_or_else(|| anyhow!("{} {} {} overflows", lhs, op.symbol(), rhs)),
+            (Dynamic::Duration(duration), Dynamic::Num(factor))
+            | (Dynamic::Num(factor), Dynamic::Duration(duration))
+                if op == BinOp::Multiply =>
+            {
+                duration.scale(*factor).map(Dynamic::Duration)
+            }
+            (Dynamic::Duration(duration), Dynamic::Num(divisor)) if op == BinOp::Divide => {
+                duration.scale(1_f64 / divisor).map(Dynamic::Duration)
+            }
+            _ => Err(anyhow::Error::new(MyError::new(&format!(
+                "`{}` expects numbers",
+                op.symbol()
+            )))),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Dynamic::Num(num) => Some(*num),
+            _ => None,
+        }
+    }
+
+    fn from_closure(closure: Closure<Self>) -> Option<Self> {
+        Some(Dynamic::Function(closure))
+    }
+
+    fn as_closure(&self) -> Option<&Closure<Self>> {
+        match self {
+            Dynamic::Function(closure) => Some(closure),
+            _ => None,
+        }
+    }
+
+    fn from_list(list: Vec<Self>) -> Option<Self> {
+        Some(Dynamic::List(list))
+    }
+
+    fn as_list(&self) -> Option<&[Self]> {
+        match self {
+            Dynamic::List(list) => Some(list),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Dynamic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dynamic::Num(num) => write!(f, "{}", num),
+            Dynamic::Date(date) => write!(f, "{}", date),
+            Dynamic::Duration(duration) => write!(f, "{}", duration),
+            Dynamic::Day(day) => write!(f, "{:?}", day),
+            Dynamic::List(list) => {
+                write!(f, "[")?;
+                for (i, element) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Dynamic::Function(closure) => write!(f, "{}", closure),
+        }
+    }
+}
+
+/// Built-in functions over lists and functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Builtin {
+    /// `range(a, b)`: the list `a, a + 1, ...` up to `b` inclusive.
+    Range,
+    /// `len(list)`.
+    Len,
+    /// `sum(list)`.
+    Sum,
+    /// `map(f, list)`.
+    Map,
+    /// `filter(f, list)`: the elements for which `f` is nonzero.
+    Filter,
+    /// `reduce(f, list)`: `f(...f(f(a, b), c)..., z)` for the list `a, b, c, ..., z`.
+    Reduce,
+}
+
+impl Builtin {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "range" => Some(Builtin::Range),
+            "len" => Some(Builtin::Len),
+            "sum" => Some(Builtin::Sum),
+            "map" => Some(Builtin::Map),
+            "filter" => Some(Builtin::Filter),
+            "reduce" => Some(Builtin::Reduce),
+            _ => None,
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Builtin::Len | Builtin::Sum => 1,
+            Builtin::Range | Builtin::Map | Builtin::Filter | Builtin::Reduce => 2,
+        }
+    }
+}
+
+/// Function a call refers to.
+#[derive(Debug)]
+enum Callee<'c, V> {
+    /// Variable holding a function.
+    Closure(V),
+    /// Registered host function.
+    Host(&'c Function<V>),
+    Builtin(Builtin),
+}
+
+impl<V> Callee<'_, V> {
+    /// Fails unless the callee takes `given` arguments. Closures check it when applied.
+    fn check_arity(&self, name: &str, given: usize) -> Result<()> {
+        let arity = match self {
+            Callee::Closure(_) => return Ok(()),
+            Callee::Host(function) => function.arity,
+            Callee::Builtin(builtin) => builtin.arity(),
+        };
+        if given != arity {
+            return Err(arity_error(&name, arity, given));
+        }
+        Ok(())
+    }
+}
+
+/// Variables visible in the body of a lambda being called: its parameters and the variables it
+/// captured.
+#[derive(Debug, Clone, Copy)]
+struct Scope<'s, V> {
+    closure: &'s Closure<V>,
+    args: &'s [V],
+}
+
+impl<V: Clone> Scope<'_, V> {
+    fn get(&self, name: &str) -> Option<V> {
+        match self.closure.params.iter().rposition(|param| param == name) {
+            Some(i) => Some(self.args[i].clone()),
+            None => self.closure.captured.get(name).cloned(),
+        }
+    }
+}
+
+/// Collects the variables and functions the expression refers to, other than `bound`.
+fn free_names<'e>(
+    expression: &'e Expression,
+    bound: &mut Vec<&'e str>,
+    names: &mut BTreeSet<&'e str>,
+) {
+    match expression {
+        Expression::Variable(name) | Expression::Call { name, .. } => {
+            if !bound.contains(&name.as_str()) {
+                let _unused = names.insert(name);
+            }
+        }
+        _ => {}
+    }
+    if let Expression::Lambda { params, body } = expression {
+        let len = bound.len();
+        bound.extend(params.iter().map(String::as_str));
+        free_names(body, bound, names);
+        bound.truncate(len);
+    } else {
+        for child in expression.children() {
+            free_names(child, bound, names);
+        }
+    }
+}
+
+fn number<V: Value>(value: &V) -> Result<f64> {
+    value
+        .as_f64()
+        .ok_or_else(|| anyhow::Error::new(MyError::new(&format!("{} is not a number", value))))
+}
+
+fn list<V: Value>(value: &V) -> Result<&[V]> {
+    value
+        .as_list()
+        .ok_or_else(|| anyhow::Error::new(MyError::new(&format!("{} is not a list", value))))
+}
+
+fn closure<V: Value>(value: &V) -> Result<&Closure<V>> {
+    value
+        .as_closure()
+        .ok_or_else(|| anyhow::Error::new(MyError::new(&format!("{} is not a function", value))))
+}
+
+fn make_list<V: Value>(list: Vec<V>) -> Result<V> {
+    V::from_list(list)
+        .ok_or_else(|| anyhow::Error::new(MyError::new("lists need a value type with lists")))
+}
+
+fn arity_error(name: &dyn fmt::Display, arity: usize, given: usize) -> anyhow::Error {
+    anyhow::Error::new(MyError::new(&format!(
+        "{} takes {} argument(s) but {} were given",
+        name, arity, given
+    )))
+}
+
+/// Body of a host function.
+type FunctionBody<V> = dyn Fn(&[V]) -> Result<V> + Send + Sync;
+
+/// Host function callable from expressions.
+#[derive(Clone)]
+struct Function<V> {
+    arity: usize,
+    body: Arc<FunctionBody<V>>,
+}
+
+impl<V> Debug for Function<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("arity", &self.arity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Assignment of a variable by [`Context::calc_command`], recorded in the context's journal.
+#[derive(Debug, Clone)]
+pub struct Assignment<V> {
+    variable: String,
+    value: V,
+    source: String,
+    /// Value of the variable before the assignment.
+    previous: Option<V>,
+    /// Anonymous variable counters before and after the assignment.
+    counters: (usize, usize),
+}
+
+impl<V> Assignment<V> {
+    /// Returns the assigned variable.
+    pub fn variable(&self) -> &str {
+        &self.variable
+    }
+
+    /// Returns the assigned value.
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    /// Returns the command that produced the value.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the value of the variable before the assignment.
+    pub fn previous(&self) -> Option<&V> {
+        self.previous.as_ref()
+    }
+}
+
+/// Default number of assignments kept in the journal.
+const DEFAULT_HISTORY_LIMIT: usize = 1024;
+
 /// Calculator's context.
-#[derive(Debug, Default, Clone)]
-pub struct Context {
+///
+/// `Context::new()` computes with `f64`; use `Context::<V>::default()` for other value types.
+#[derive(Debug, Clone)]
+pub struct Context<V = f64> {
     anonymous_counter: usize,
-    variables: HashMap<String, f64>,
+    variables: HashMap<String, V>,
+    functions: HashMap<String, Function<V>>,
+    limits: Limits,
+    /// Assignments that can be undone, from the oldest to the latest.
+    journal: VecDeque<Assignment<V>>,
+    /// Undone assignments that can be redone, from the latest to the oldest.
+    undone: Vec<Assignment<V>>,
+    history_limit: usize,
+}
+
+impl<V> Default for Context<V> {
+    fn default() -> Self {
+        Self {
+            anonymous_counter: 0,
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+            limits: Limits::default(),
+            journal: VecDeque::new(),
+            undone: Vec::new(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+        }
+    }
 }
 
 impl Context {
@@ -47,36 +517,260 @@ impl Context {
     pub fn new() -> Self {
         Self::default()
     }
+}
 
+impl<V: Value> Context<V> {
     /// Returns the current anonymous variable counter.
     pub fn current_counter(&self) -> usize {
         self.anonymous_counter
     }
 
+    /// Sets the variable, overwriting its previous value if any.
+    pub fn set_var(&mut self, name: &str, value: V) -> &mut Self {
+        let _out = self.variables.insert(name.to_string(), value);
+        self
+    }
+
+    /// Returns the value of the variable.
+    pub fn get_var(&self, name: &str) -> Option<&V> {
+        self.variables.get(name)
+    }
+
+    /// Removes the variable, returning its value.
+    pub fn remove_var(&mut self, name: &str) -> Option<V> {
+        self.variables.remove(name)
+    }
+
+    /// Iterates over the variables and their values in arbitrary order.
+    pub fn variables(&self) -> impl Iterator<Item = (&str, &V)> {
+        self.variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Registers the function `name` taking `arity` arguments, replacing the previous one if any.
+    ///
+    /// Calls with a wrong number of arguments are rejected before `body` is invoked, and errors
+    /// returned by `body` are propagated to the caller of [`Context::calc_expression`].
+    ///
+    /// # Example
+    ///
+    /// After `context.register_fn("max", 2, |args| Ok(args[0].max(args[1])))`, command `max(3, 5)`
+    /// evaluates to `5`.
+    pub fn register_fn<F>(&mut self, name: &str, arity: usize, body: F) -> &mut Self
+    where
+        F: Fn(&[V]) -> Result<V> + Send + Sync + 'static,
+    {
+        let function = Function {
+            arity,
+            body: Arc::new(body),
+        };
+        let _out = self.functions.insert(name.to_string(), function);
+        self
+    }
+
+    /// Iterates over the registered function names and their arities in arbitrary order.
+    pub fn functions(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.functions
+            .iter()
+            .map(|(name, function)| (name.as_str(), function.arity))
+    }
+
+    /// Sets the resource limits for evaluating expressions.
+    pub fn set_limits(&mut self, limits: Limits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the resource limits for evaluating expressions.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
     /// Calculates the given expression. (We assume the absence of overflow.)
-    pub fn calc_expression(&self, expression: &Expression) -> Result<f64> {
-        match expression {
+    ///
+    /// Fails with [`LimitExceeded`](super::limits::LimitExceeded) if the expression exceeds the
+    /// context's limits. Host functions are not interrupted by the wall time limit.
+    pub fn calc_expression(&self, expression: &Expression) -> Result<V> {
+        self.limits.check_expression(expression)?;
+        self.calc_expression_with_budget(expression, None, &mut Budget::new(&self.limits))
+    }
+
+    /// Returns the value of the variable, looking into the scope of the lambda being called if
+    /// any. The bodies of lambdas only see their parameters and captured variables.
+    fn lookup(&self, name: &str, scope: Option<&Scope<'_, V>>) -> Option<V> {
+        match scope {
+            Some(scope) => scope.get(name),
+            None => self.variables.get(name).cloned(),
+        }
+    }
+
+    /// Returns the function called `name`. Variables holding functions shadow registered
+    /// functions, which shadow the built-ins.
+    fn callee(&self, name: &str, scope: Option<&Scope<'_, V>>) -> Result<Callee<'_, V>> {
+        if let Some(value) = self.lookup(name, scope) {
+            if value.as_closure().is_some() {
+                return Ok(Callee::Closure(value));
+            }
+        }
+        if let Some(function) = self.functions.get(name) {
+            return Ok(Callee::Host(function));
+        }
+        match Builtin::from_name(name) {
+            Some(builtin) => Ok(Callee::Builtin(builtin)),
+            None => Err(anyhow::Error::new(MyError::new("fn not in the hashmap"))),
+        }
+    }
+
+    fn calc_expression_with_budget(
+        &self,
+        expression: &Expression,
+        scope: Option<&Scope<'_, V>>,
+        budget: &mut Budget,
+    ) -> Result<V> {
+        budget.step()?;
+        // An error abandons the whole evaluation, so only successful evaluations leave.
+        budget.enter()?;
+        let value = match expression {
             Expression::Variable(var) => {
-                let val = self.variables.get(var);
+                let val = self.lookup(var, scope);
                 match val {
-                    Some(val) => Ok(*val),
+                    Some(val) => Ok(val),
                     None => Err(anyhow::Error::new(MyError::new("var not in the hashmap"))),
                 }
             }
-            Expression::Num(num) => Ok(*num),
-            Expression::BinOp { op, lhs, rhs } => match op {
-                BinOp::Add => Ok(self.calc_expression(lhs)? + self.calc_expression(rhs)?),
-                BinOp::Subtract => Ok(self.calc_expression(lhs)? - self.calc_expression(rhs)?),
-                BinOp::Multiply => Ok(self.calc_expression(lhs)? * self.calc_expression(rhs)?),
-                BinOp::Divide => {
-                    if self.calc_expression(rhs)? == 0_f64 {
-                        Err(anyhow::Error::new(MyError::new("cannot divide 0")))
-                    } else {
-                        Ok(self.calc_expression(lhs)? / self.calc_expression(rhs)?)
+            Expression::Num(num) => Ok(V::from_num(*num)),
+            Expression::BinOp { op, lhs, rhs } => {
+                let percent = rhs.is_percent_of(*op);
+                let lhs = self.calc_expression_with_budget(lhs, scope, budget)?;
+                let mut rhs = self.calc_expression_with_budget(rhs, scope, budget)?;
+                if percent {
+                    rhs = V::calc_binop(BinOp::Multiply, &lhs, &rhs)?;
+                }
+                if *op == BinOp::Power {
+                    self.limits.check_power(lhs.as_f64(), rhs.as_f64())?;
+                }
+                V::calc_binop(*op, &lhs, &rhs)
+            }
+            Expression::UnOp { op, expr } => {
+                let operand = self.calc_expression_with_budget(expr, scope, budget)?;
+                V::calc_unop(*op, &operand)
+            }
+            Expression::Call { name, args } => {
+                let callee = self.callee(name, scope)?;
+                callee.check_arity(name, args.len())?;
+                let args = args
+                    .iter()
+                    .map(|arg| self.calc_expression_with_budget(arg, scope, budget))
+                    .collect::<Result<Vec<_>>>()?;
+                self.invoke(callee, &args, budget)
+            }
+            Expression::List(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.calc_expression_with_budget(element, scope, budget))
+                    .collect::<Result<Vec<_>>>()?;
+                make_list(elements)
+            }
+            Expression::Lambda { params, body } => {
+                let mut names = BTreeSet::new();
+                free_names(
+                    body,
+                    &mut params.iter().map(String::as_str).collect(),
+                    &mut names,
+                );
+                let captured = names
+                    .into_iter()
+                    .filter_map(|name| Some((name.to_string(), self.lookup(name, scope)?)))
+                    .collect();
+                let closure = Closure {
+                    params: params.clone(),
+                    body: Arc::new((**body).clone()),
+                    captured: Arc::new(captured),
+                };
+                V::from_closure(closure).ok_or_else(|| {
+                    anyhow::Error::new(MyError::new("lambdas need a value type with functions"))
+                })
+            }
+        }?;
+        budget.leave();
+        self.limits.check_integer(value.as_f64())?;
+        Ok(value)
+    }
+
+    /// Calls the function called `name` with calculated arguments.
+    pub(crate) fn call(&self, name: &str, args: &[V], budget: &mut Budget) -> Result<V> {
+        let callee = self.callee(name, None)?;
+        callee.check_arity(name, args.len())?;
+        self.invoke(callee, args, budget)
+    }
+
+    fn invoke(&self, callee: Callee<'_, V>, args: &[V], budget: &mut Budget) -> Result<V> {
+        match callee {
+            Callee::Closure(value) => self.apply(closure(&value)?, args, budget),
+            Callee::Host(function) => (function.body)(args),
+            Callee::Builtin(builtin) => self.call_builtin(builtin, args, budget),
+        }
+    }
+
+    /// Calls the function created by a lambda expression.
+    fn apply(&self, closure: &Closure<V>, args: &[V], budget: &mut Budget) -> Result<V> {
+        if args.len() != closure.params.len() {
+            return Err(arity_error(closure, closure.params.len(), args.len()));
+        }
+        let scope = Scope { closure, args };
+        self.calc_expression_with_budget(&closure.body, Some(&scope), budget)
+    }
+
+    fn call_builtin(&self, builtin: Builtin, args: &[V], budget: &mut Budget) -> Result<V> {
+        match builtin {
+            Builtin::Range => {
+                let (start, end) = (number(&args[0])?, number(&args[1])?);
+                let mut elements = Vec::new();
+                let mut element = start;
+                while element <= end {
+                    budget.step()?;
+                    elements.push(V::from_num(element));
+                    element += 1_f64;
+                }
+                make_list(elements)
+            }
+            Builtin::Len => Ok(V::from_num(list(&args[0])?.len() as f64)),
+            Builtin::Sum => list(&args[0])?
+                .iter()
+                .try_fold(V::from_num(0_f64), |sum, element| {
+                    budget.step()?;
+                    V::calc_binop(BinOp::Add, &sum, element)
+                }),
+            Builtin::Map => {
+                let function = closure(&args[0])?;
+                let elements = list(&args[1])?
+                    .iter()
+                    .map(|element| self.apply(function, slice::from_ref(element), budget))
+                    .collect::<Result<Vec<_>>>()?;
+                make_list(elements)
+            }
+            Builtin::Filter => {
+                let function = closure(&args[0])?;
+                let mut elements = Vec::new();
+                for element in list(&args[1])? {
+                    let keep = self.apply(function, slice::from_ref(element), budget)?;
+                    if number(&keep)? != 0_f64 {
+                        elements.push(element.clone());
                     }
                 }
-                BinOp::Power => Ok(self.calc_expression(lhs)?.powf(self.calc_expression(rhs)?)),
-            },
+                make_list(elements)
+            }
+            Builtin::Reduce => {
+                let function = closure(&args[0])?;
+                let mut elements = list(&args[1])?.iter();
+                let first = elements
+                    .next()
+                    .ok_or_else(|| anyhow::Error::new(MyError::new("reduce of an empty list")))?;
+                elements.try_fold(first.clone(), |accumulated, element| {
+                    self.apply(function, &[accumulated, element.clone()], budget)
+                })
+            }
         }
     }
 
@@ -91,7 +785,20 @@ impl Context {
     /// After calculating commad `v = 3 - 2` => Context's variables = `{($0,8),(v,1))}`
     ///
     /// After calculating commad `3 ^ 2` => Context's variables = `{($0,8),(v,1),($1,9)}`
-    pub fn calc_command(&mut self, command: &Command) -> Result<(String, f64)> {
+    ///
+    /// The assignment is recorded in the journal, so that it can be undone.
+    pub fn calc_command(&mut self, command: &Command) -> Result<(String, V)> {
+        self.calc_command_with_source(command, &command.to_string())
+    }
+
+    /// Calculates the given command as [`Context::calc_command`] does, recording `source` as the
+    /// command that produced the value, e.g. the line the command was rewritten from.
+    pub fn calc_command_with_source(
+        &mut self,
+        command: &Command,
+        source: &str,
+    ) -> Result<(String, V)> {
+        let counter = self.anonymous_counter;
         let key = match command.variable.clone() {
             Some(name) => name,
             None => {
@@ -101,7 +808,64 @@ impl Context {
             }
         };
         let value = self.calc_expression(&command.expression)?;
-        let _out = self.variables.insert(key.clone(), value);
+        let previous = self.variables.insert(key.clone(), value.clone());
+
+        self.undone.clear();
+        self.journal.push_back(Assignment {
+            variable: key.clone(),
+            value: value.clone(),
+            source: source.to_string(),
+            previous,
+            counters: (counter, self.anonymous_counter),
+        });
+        while self.journal.len() > self.history_limit {
+            let _out = self.journal.pop_front();
+        }
         Ok((key, value))
     }
+
+    /// Sets the number of assignments kept for undoing and [`Context::history`]. Older assignments
+    /// are forgotten.
+    pub fn set_history_limit(&mut self, limit: usize) -> &mut Self {
+        self.history_limit = limit;
+        while self.journal.len() > limit {
+            let _out = self.journal.pop_front();
+        }
+        self
+    }
+
+    /// Undoes the latest assignment by [`Context::calc_command`], restoring the variable's previous
+    /// value. Returns the undone assignment.
+    ///
+    /// Variables set by the host with [`Context::set_var`] are not journaled.
+    pub fn undo(&mut self) -> Option<&Assignment<V>> {
+        let assignment = self.journal.pop_back()?;
+        let _out = match &assignment.previous {
+            Some(previous) => self
+                .variables
+                .insert(assignment.variable.clone(), previous.clone()),
+            None => self.variables.remove(&assignment.variable),
+        };
+        self.anonymous_counter = assignment.counters.0;
+        self.undone.push(assignment);
+        self.undone.last()
+    }
+
+    /// Redoes the latest undone assignment. Returns the redone assignment.
+    pub fn redo(&mut self) -> Option<&Assignment<V>> {
+        let assignment = self.undone.pop()?;
+        let _out = self
+            .variables
+            .insert(assignment.variable.clone(), assignment.value.clone());
+        self.anonymous_counter = assignment.counters.1;
+        self.journal.push_back(assignment);
+        self.journal.back()
+    }
+
+    /// Iterates over the journaled assignments of the variable, from the oldest to the latest.
+    pub fn history<'s>(&'s self, variable: &'s str) -> impl Iterator<Item = &'s Assignment<V>> {
+        self.journal
+            .iter()
+            .filter(move |assignment| assignment.variable == variable)
+    }
 }
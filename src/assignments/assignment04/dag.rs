@@ -0,0 +1,349 @@
+//! Expressions as directed acyclic graphs.
+//!
+//! Generated formulas often repeat the same subexpression many times. A [`Dag`] stores each
+//! distinct subexpression once (hash-consing): inserting an expression that is structurally equal
+//! to one already in the graph returns the existing node. Evaluating a node then computes each
+//! shared subexpression once, and [`Dag::to_expression`] turns it back into a tree.
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+use anyhow::*;
+
+use super::context::{Context, Value};
+use super::limits::Budget;
+use super::syntax::{BinOp, Expression, UnOp};
+
+/// Node of a [`Dag`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// Subexpression whose operands are other nodes of the graph.
+///
+/// Numbers are compared by their bits, so that `0` and `-0` are different nodes while NaN is
+/// equal to itself.
+#[derive(Debug, Clone)]
+pub enum Node {
+    /// Number.
+    Num(f64),
+    /// Variable.
+    Variable(String),
+    /// Binary operation.
+    BinOp {
+        /// Operator.
+        op: BinOp,
+        /// Lhs.
+        lhs: NodeId,
+        /// Rhs.
+        rhs: NodeId,
+    },
+    /// Unary operation.
+    UnOp {
+        /// Operator.
+        op: UnOp,
+        /// Operand.
+        expr: NodeId,
+    },
+    /// Function call.
+    Call {
+        /// Function name.
+        name: String,
+        /// Arguments.
+        args: Vec<NodeId>,
+    },
+    /// List.
+    List(Vec<NodeId>),
+    /// Anonymous function.
+    Lambda {
+        /// Parameters.
+        params: Vec<String>,
+        /// Body.
+        body: NodeId,
+    },
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Node::Num(lhs), Node::Num(rhs)) => lhs.to_bits() == rhs.to_bits(),
+            (Node::Variable(lhs), Node::Variable(rhs)) => lhs == rhs,
+            (
+                Node::BinOp { op, lhs, rhs },
+                Node::BinOp {
+                    op: op2,
+                    lhs: lhs2,
+                    rhs: rhs2,
+                },
+            ) => (op, lhs, rhs) == (op2, lhs2, rhs2),
+            (
+                Node::UnOp { op, expr },
+                Node::UnOp {
+                    op: op2,
+                    expr: expr2,
+                },
+            ) => (op, expr) == (op2, expr2),
+            (
+                Node::Call { name, args },
+                Node::Call {
+                    name: name2,
+                    args: args2,
+                },
+            ) => (name, args) == (name2, args2),
+            (Node::List(lhs), Node::List(rhs)) => lhs == rhs,
+            (
+                Node::Lambda { params, body },
+                Node::Lambda {
+                    params: params2,
+                    body: body2,
+                },
+            ) => (params, body) == (params2, body2),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Node {}
+
+impl Hash for Node {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Node::Num(num) => num.to_bits().hash(state),
+            Node::Variable(var) => var.hash(state),
+            Node::BinOp { op, lhs, rhs } => (op, lhs, rhs).hash(state),
+            Node::UnOp { op, expr } => (op, expr).hash(state),
+            Node::Call { name, args } => (name, args).hash(state),
+            Node::List(elements) => elements.hash(state),
+            Node::Lambda { params, body } => (params, body).hash(state),
+        }
+    }
+}
+
+impl Node {
+    /// Returns the operands.
+    pub fn children(&self) -> Vec<NodeId> {
+        match self {
+            Node::Num(_) | Node::Variable(_) => vec![],
+            Node::BinOp { lhs, rhs, .. } => vec![*lhs, *rhs],
+            Node::UnOp { expr, .. } => vec![*expr],
+            Node::Call { args, .. } | Node::List(args) => args.clone(),
+            Node::Lambda { body, .. } => vec![*body],
+        }
+    }
+
+    /// Returns whether the node is a percentage `b%` of the lhs of `op`, see
+    /// [`BinOp::takes_percent_of`].
+    pub fn is_percent_of(&self, op: BinOp) -> bool {
+        op.takes_percent_of()
+            && matches!(
+                self,
+                Node::UnOp {
+                    op: UnOp::Percent,
+                    ..
+                }
+            )
+    }
+}
+
+/// Graph of interned subexpressions, possibly shared by several expressions.
+///
+/// Operands are inserted before the nodes using them, so a node's operands always have smaller
+/// ids.
+#[derive(Debug, Clone, Default)]
+pub struct Dag {
+    nodes: Vec<Node>,
+    ids: HashMap<Node, NodeId>,
+}
+
+impl Dag {
+    /// Creates an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of nodes in the graph.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns whether the graph has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the node.
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// Inserts the expression, reusing the nodes of structurally equal subexpressions, and
+    /// returns its node.
+    pub fn insert(&mut self, expression: &Expression) -> NodeId {
+        let node = match expression {
+            Expression::Num(num) => Node::Num(*num),
+            Expression::Variable(var) => Node::Variable(var.clone()),
+            Expression::BinOp { op, lhs, rhs } => Node::BinOp {
+                op: *op,
+                lhs: self.insert(lhs),
+                rhs: self.insert(rhs),
+            },
+            Expression::UnOp { op, expr } => Node::UnOp {
+                op: *op,
+                expr: self.insert(expr),
+            },
+            Expression::Call { name, args } => Node::Call {
+                name: name.clone(),
+                args: args.iter().map(|arg| self.insert(arg)).collect(),
+            },
+            Expression::List(elements) => Node::List(
+                elements
+                    .iter()
+                    .map(|element| self.insert(element))
+                    .collect(),
+            ),
+            Expression::Lambda { params, body } => Node::Lambda {
+                params: params.clone(),
+                body: self.insert(body),
+            },
+        };
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(node.clone());
+        let _ = self.ids.insert(node, id);
+        id
+    }
+
+    /// Converts the node back into a tree, copying shared subexpressions.
+    pub fn to_expression(&self, id: NodeId) -> Expression {
+        match self.node(id) {
+            Node::Num(num) => Expression::Num(*num),
+            Node::Variable(var) => Expression::Variable(var.clone()),
+            Node::BinOp { op, lhs, rhs } => Expression::BinOp {
+                op: *op,
+                lhs: Box::new(self.to_expression(*lhs)),
+                rhs: Box::new(self.to_expression(*rhs)),
+            },
+            Node::UnOp { op, expr } => Expression::UnOp {
+                op: *op,
+                expr: Box::new(self.to_expression(*expr)),
+            },
+            Node::Call { name, args } => Expression::Call {
+                name: name.clone(),
+                args: args.iter().map(|arg| self.to_expression(*arg)).collect(),
+            },
+            Node::List(elements) => Expression::List(
+                elements
+                    .iter()
+                    .map(|element| self.to_expression(*element))
+                    .collect(),
+            ),
+            Node::Lambda { params, body } => Expression::Lambda {
+                params: params.clone(),
+                body: Box::new(self.to_expression(*body)),
+            },
+        }
+    }
+
+    /// Returns the number of distinct nodes reachable from the node, i.e., its size as a graph.
+    pub fn size(&self, id: NodeId) -> usize {
+        let mut reached = vec![false; id.0 + 1];
+        reached[id.0] = true;
+        // Operands have smaller ids, so visiting in decreasing order reaches every node.
+        for i in (0..=id.0).rev() {
+            if reached[i] {
+                for child in self.nodes[i].children() {
+                    reached[child.0] = true;
+                }
+            }
+        }
+        reached.into_iter().filter(|reached| *reached).count()
+    }
+
+    /// Returns the number of nodes of the node's tree form, without building it. It saturates at
+    /// `usize::MAX`, as the tree may be exponentially larger than the graph.
+    pub fn tree_size(&self, id: NodeId) -> usize {
+        let mut sizes = Vec::with_capacity(id.0 + 1);
+        for node in &self.nodes[..=id.0] {
+            let size = node
+                .children()
+                .into_iter()
+                .fold(1_usize, |size, child| size.saturating_add(sizes[child.0]));
+            sizes.push(size);
+        }
+        sizes[id.0]
+    }
+
+    /// Calculates the node in the context, computing each shared subexpression once.
+    ///
+    /// The result is the same as that of calculating the tree form with
+    /// [`Context::calc_expression`], except that each distinct subexpression counts as a single
+    /// step towards the context's limits.
+    pub fn calc<V: Value>(&self, id: NodeId, context: &Context<V>) -> Result<V> {
+        let mut values = vec![None; id.0 + 1];
+        let mut budget = Budget::new(context.limits());
+        self.calc_node(id, context, &mut values, &mut budget)
+    }
+
+    fn calc_node<V: Value>(
+        &self,
+        id: NodeId,
+        context: &Context<V>,
+        values: &mut Vec<Option<V>>,
+        budget: &mut Budget,
+    ) -> Result<V> {
+        if let Some(value) = &values[id.0] {
+            return Ok(value.clone());
+        }
+        budget.step()?;
+        budget.enter()?;
+        let value = match self.node(id) {
+            Node::Num(num) => V::from_num(*num),
+            Node::Variable(var) => match context.get_var(var) {
+                Some(value) => value.clone(),
+                None => bail!("var not in the hashmap"),
+            },
+            Node::BinOp { op, lhs, rhs } => {
+                let percent = self.node(*rhs).is_percent_of(*op);
+                let lhs = self.calc_node(*lhs, context, values, budget)?;
+                let mut rhs = self.calc_node(*rhs, context, values, budget)?;
+                if percent {
+                    rhs = V::calc_binop(BinOp::Multiply, &lhs, &rhs)?;
+                }
+                if *op == BinOp::Power {
+                    context.limits().check_power(lhs.as_f64(), rhs.as_f64())?;
+                }
+                V::calc_binop(*op, &lhs, &rhs)?
+            }
+            Node::UnOp { op, expr } => {
+                let operand = self.calc_node(*expr, context, values, budget)?;
+                V::calc_unop(*op, &operand)?
+            }
+            Node::Call { name, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.calc_node(*arg, context, values, budget))
+                    .collect::<Result<Vec<_>>>()?;
+                context.call(name, &args, budget)?
+            }
+            Node::List(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.calc_node(*element, context, values, budget))
+                    .collect::<Result<Vec<_>>>()?;
+                match V::from_list(elements) {
+                    Some(list) => list,
+                    None => bail!("lists need a value type with lists"),
+                }
+            }
+            // The body is evaluated only when the lambda is called, with its parameters bound.
+            Node::Lambda { .. } => context.calc_expression(&self.to_expression(id))?,
+        };
+        budget.leave();
+        context.limits().check_integer(value.as_f64())?;
+        values[id.0] = Some(value.clone());
+        Ok(value)
+    }
+}
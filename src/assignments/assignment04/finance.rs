@@ -0,0 +1,148 @@
+//! Financial functions.
+//!
+//! They follow the spreadsheet conventions: `rate` is the interest rate per period, payments are
+//! made at the end of each period, and money paid out is negative, e.g. borrowing `200000` at 6% a
+//! year over 30 years costs `pmt(6% / 12, 360, 200000)`, i.e., `-1199.10` a month.
+
+use anyhow::*;
+
+use super::context::{Context, Dynamic};
+use super::statistics::numbers;
+
+/// Maximum number of Newton steps of [`irr`], before it falls back to bisection.
+const MAX_NEWTON_STEPS: usize = 50;
+
+/// Relative precision of [`irr`].
+const TOLERANCE: f64 = 1e-12;
+
+/// Returns `((1 + rate) ^ nper, ((1 + rate) ^ nper - 1) / rate)`, the growth of a present value
+/// and of a series of payments of 1.
+fn growth(rate: f64, nper: f64) -> (f64, f64) {
+    if rate == 0_f64 {
+        return (1_f64, nper);
+    }
+    let interest = (rate.ln_1p() * nper).exp_m1();
+    (1_f64 + interest, interest / rate)
+}
+
+/// Annuity function of `rate`, `nper`, an amount, and a present or future value.
+type Annuity = fn(f64, f64, f64, f64) -> f64;
+
+/// Returns the future value of the present value `pv` and `nper` payments of `pmt`.
+pub fn fv(rate: f64, nper: f64, pmt: f64, pv: f64) -> f64 {
+    let (growth, annuity) = growth(rate, nper);
+    -(pv * growth + pmt * annuity)
+}
+
+/// Returns the present value of `nper` payments of `pmt` and the future value `fv`.
+pub fn pv(rate: f64, nper: f64, pmt: f64, fv: f64) -> f64 {
+    let (growth, annuity) = growth(rate, nper);
+    -(fv + pmt * annuity) / growth
+}
+
+/// Returns the payment per period that, over `nper` periods, turns the present value `pv` into the
+/// future value `fv`.
+pub fn pmt(rate: f64, nper: f64, pv: f64, fv: f64) -> f64 {
+    let (growth, annuity) = growth(rate, nper);
+    -(pv * growth + fv) / annuity
+}
+
+/// Returns the net present value of the cash flows, the first of which is at the end of the first
+/// period.
+pub fn npv(rate: f64, values: &[f64]) -> f64 {
+    values
+        .iter()
+        .rev()
+        .fold(0_f64, |npv, value| (npv + value) / (1_f64 + rate))
+}
+
+/// Returns the internal rate of return of the cash flows, the first of which is now, i.e., the
+/// rate at which their net present value is zero.
+///
+/// Newton's method starts from 10%, and bisection takes over if it does not converge. Returns
+/// `None` unless the cash flows both pay and receive money.
+pub fn irr(values: &[f64]) -> Option<f64> {
+    if !values.iter().any(|value| *value > 0_f64) || !values.iter().any(|value| *value < 0_f64) {
+        return None;
+    }
+    let net = |rate: f64| values[0] + npv(rate, &values[1..]);
+    let derivative = |rate: f64| {
+        values
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, value)| -(i as f64) * value / (1_f64 + rate).powi(i as i32 + 1))
+            .sum::<f64>()
+    };
+
+    let mut rate = 0.1_f64;
+    for _ in 0..MAX_NEWTON_STEPS {
+        let step = net(rate) / derivative(rate);
+        if !step.is_finite() || rate - step <= -1_f64 {
+            break;
+        }
+        rate -= step;
+        if step.abs() <= TOLERANCE * rate.abs().max(1_f64) {
+            return Some(rate);
+        }
+    }
+
+    // Brackets a root between rates just above -100% and doubling upper bounds.
+    let (mut low, mut high) = (-1_f64 + 1e-9, 1_f64);
+    if !net(low).is_finite() {
+        return None;
+    }
+    while net(low).signum() == net(high).signum() {
+        high *= 2_f64;
+        if high > 1e9 {
+            return None;
+        }
+    }
+    while high - low > TOLERANCE * high.abs().max(1_f64) {
+        let middle = (low + high) / 2_f64;
+        if net(middle).signum() == net(low).signum() {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    Some((low + high) / 2_f64)
+}
+
+/// Returns the argument of the function as a number.
+fn number(name: &str, arg: &Dynamic) -> Result<f64> {
+    match arg {
+        Dynamic::Num(num) => Ok(*num),
+        _ => bail!("{} expects a number, but got {}", name, arg),
+    }
+}
+
+/// Registers `pmt(rate, nper, pv)`, `pv(rate, nper, pmt)`, `fv(rate, nper, pmt)`,
+/// `npv(rate, list)`, and `irr(list)`.
+pub fn register(context: &mut Context<Dynamic>) -> &mut Context<Dynamic> {
+    let annuities: [(&str, Annuity); 3] = [("pmt", pmt), ("pv", pv), ("fv", fv)];
+    for (name, function) in annuities {
+        let _ = context.register_fn(name, 3, move |args| {
+            let (rate, nper, amount) = (
+                number(name, &args[0])?,
+                number(name, &args[1])?,
+                number(name, &args[2])?,
+            );
+            if rate <= -1_f64 {
+                bail!("{} of the rate {}, which is not above -100%", name, rate);
+            }
+            Ok(Dynamic::Num(function(rate, nper, amount, 0_f64)))
+        });
+    }
+    let _ = context.register_fn("npv", 2, |args| {
+        let rate = number("npv", &args[0])?;
+        if rate <= -1_f64 {
+            bail!("npv of the rate {}, which is not above -100%", rate);
+        }
+        Ok(Dynamic::Num(npv(rate, &numbers("npv", &args[1])?)))
+    });
+    context.register_fn("irr", 1, |args| match irr(&numbers("irr", &args[0])?) {
+        Some(rate) => Ok(Dynamic::Num(rate)),
+        None => bail!("irr of {}, which has no rate of return", args[0]),
+    })
+}
@@ -0,0 +1,492 @@
+//! Fixed-width integers for programmer mode.
+//!
+//! Values of type `u8`, ..., `u64`, `usize`, `i8`, ..., `i64`, or `isize` are computed exactly and
+//! then brought back into the range of their type according to its [`Overflow`] mode: wrapping
+//! around like [`assignment01::add`](crate::assignments::assignment01::add) does for `usize`,
+//! failing, or saturating. Literals get their type from a suffix (`255u8`, which parses as the cast
+//! `u8(255)`), or are inferred by [`infer_types`]. Integers without a type, e.g. those
+//! returned by `len`, take the type of the other operand.
+
+use std::fmt;
+use std::result::Result::Ok;
+
+use anyhow::*;
+
+use super::context::{Context, Value};
+use super::syntax::{BinOp, Expression, UnOp};
+
+/// Fixed-width integer type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntType {
+    /// `u8`.
+    U8,
+    /// `u16`.
+    U16,
+    /// `u32`.
+    U32,
+    /// `u64`.
+    U64,
+    /// `usize`.
+    Usize,
+    /// `i8`.
+    I8,
+    /// `i16`.
+    I16,
+    /// `i32`.
+    I32,
+    /// `i64`.
+    I64,
+    /// `isize`.
+    Isize,
+}
+
+impl IntType {
+    /// All integer types.
+    pub const ALL: [IntType; 10] = [
+        IntType::U8,
+        IntType::U16,
+        IntType::U32,
+        IntType::U64,
+        IntType::Usize,
+        IntType::I8,
+        IntType::I16,
+        IntType::I32,
+        IntType::I64,
+        IntType::Isize,
+    ];
+
+    /// Returns the type's name, which is also the literal suffix and the cast function.
+    pub fn name(self) -> &'static str {
+        match self {
+            IntType::U8 => "u8",
+            IntType::U16 => "u16",
+            IntType::U32 => "u32",
+            IntType::U64 => "u64",
+            IntType::Usize => "usize",
+            IntType::I8 => "i8",
+            IntType::I16 => "i16",
+            IntType::I32 => "i32",
+            IntType::I64 => "i64",
+            IntType::Isize => "isize",
+        }
+    }
+
+    /// Returns the type with the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ty| ty.name() == name)
+    }
+
+    /// Returns the number of bits.
+    pub fn bits(self) -> u32 {
+        match self {
+            IntType::U8 | IntType::I8 => 8,
+            IntType::U16 | IntType::I16 => 16,
+            IntType::U32 | IntType::I32 => 32,
+            IntType::U64 | IntType::I64 => 64,
+            IntType::Usize | IntType::Isize => usize::BITS,
+        }
+    }
+
+    /// Returns whether the type is signed.
+    pub fn is_signed(self) -> bool {
+        matches!(
+            self,
+            IntType::I8 | IntType::I16 | IntType::I32 | IntType::I64 | IntType::Isize
+        )
+    }
+
+    /// Returns the smallest value.
+    pub fn min(self) -> i128 {
+        if self.is_signed() {
+            -(1 << (self.bits() - 1))
+        } else {
+            0
+        }
+    }
+
+    /// Returns the largest value.
+    pub fn max(self) -> i128 {
+        if self.is_signed() {
+            (1 << (self.bits() - 1)) - 1
+        } else {
+            (1 << self.bits()) - 1
+        }
+    }
+
+    /// Returns whether the value is in the type's range.
+    pub fn contains(self, value: i128) -> bool {
+        (self.min()..=self.max()).contains(&value)
+    }
+
+    /// Returns the value modulo `2^bits`, in the type's range.
+    pub fn wrap(self, value: i128) -> i128 {
+        let shift = 128 - self.bits();
+        if self.is_signed() {
+            (value << shift) >> shift
+        } else {
+            ((value as u128) << shift >> shift) as i128
+        }
+    }
+}
+
+impl fmt::Display for IntType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// What happens when a result does not fit in its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wraps around modulo `2^bits`.
+    Wrapping,
+    /// Fails.
+    Checked,
+    /// Clamps to the type's range.
+    Saturating,
+}
+
+/// Integer, possibly of a fixed-width type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Int {
+    value: i128,
+    ty: Option<IntType>,
+    overflow: Overflow,
+    /// Whether a result wrapped or saturated while calculating the value.
+    overflowed: bool,
+}
+
+/// Result of an operation, before it is brought into the range of its type.
+#[derive(Debug, Clone, Copy)]
+enum Exact {
+    /// The exact result.
+    Value(i128),
+    /// The exact result overflows `i128`. The fields are the result modulo `2^128` and whether
+    /// the result is negative.
+    Huge(i128, bool),
+}
+
+impl Int {
+    /// Creates an integer of the given type, bringing `value` into its range.
+    pub fn new(value: i128, ty: IntType, overflow: Overflow) -> Result<Self> {
+        Self::untyped(value).cast(ty, overflow)
+    }
+
+    /// Creates an integer without a type.
+    pub fn untyped(value: i128) -> Self {
+        Self {
+            value,
+            ty: None,
+            overflow: Overflow::Checked,
+            overflowed: false,
+        }
+    }
+
+    /// Returns the value.
+    pub fn value(&self) -> i128 {
+        self.value
+    }
+
+    /// Returns the type, if any.
+    pub fn ty(&self) -> Option<IntType> {
+        self.ty
+    }
+
+    /// Returns whether a result wrapped or saturated while calculating the value.
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    /// Converts the integer to the type, like `as` if `overflow` is [`Overflow::Wrapping`].
+    pub fn cast(&self, ty: IntType, overflow: Overflow) -> Result<Self> {
+        let int = Self {
+            ty: Some(ty),
+            overflow,
+            ..*self
+        };
+        int.resolve(
+            Exact::Value(self.value),
+            &format!("{} as {}", self.value, ty),
+        )
+    }
+
+    /// Brings the exact result of the operation described by `what` into the range of the type.
+    fn resolve(self, exact: Exact, what: &str) -> Result<Self> {
+        let (ty, value) = match (self.ty, exact) {
+            (Some(ty), Exact::Value(value)) if ty.contains(value) => {
+                return Ok(Self { value, ..self });
+            }
+            (None, Exact::Value(value)) => return Ok(Self { value, ..self }),
+            (None, Exact::Huge(..)) => bail!("{} overflows i128", what),
+            (Some(ty), exact) => (ty, exact),
+        };
+        let value = match (self.overflow, value) {
+            (Overflow::Checked, _) => bail!("{} overflows {}", what, ty),
+            (Overflow::Wrapping, Exact::Value(value) | Exact::Huge(value, _)) => ty.wrap(value),
+            (Overflow::Saturating, Exact::Value(value)) => value.clamp(ty.min(), ty.max()),
+            (Overflow::Saturating, Exact::Huge(_, negative)) => {
+                if negative {
+                    ty.min()
+                } else {
+                    ty.max()
+                }
+            }
+        };
+        Ok(Self {
+            value,
+            overflowed: true,
+            ..self
+        })
+    }
+}
+
+/// Returns the exact `base ^ exponent`.
+fn pow(base: i128, exponent: u128) -> Exact {
+    let negative = base < 0 && exponent % 2 == 1;
+    let exact = u32::try_from(exponent)
+        .ok()
+        .and_then(|exponent| base.checked_pow(exponent));
+    if let Some(value) = exact {
+        return Exact::Value(value);
+    }
+    // Exponentiation by squaring, modulo `2^128`.
+    let (mut base, mut exponent, mut result) = (base, exponent, 1_i128);
+    while exponent > 0 && result != 0 {
+        if exponent % 2 == 1 {
+            result = result.wrapping_mul(base);
+        }
+        base = base.wrapping_mul(base);
+        exponent /= 2;
+    }
+    Exact::Huge(result, negative)
+}
+
+/// Returns the exact `n!`.
+fn factorial(n: i128) -> Exact {
+    let (mut exact, mut modular) = (Some(1_i128), 1_i128);
+    let mut i = 2;
+    // Past `2^128 | n!`, the product stays 0 modulo `2^128`.
+    while i <= n && modular != 0 {
+        exact = exact.and_then(|exact| exact.checked_mul(i));
+        modular = modular.wrapping_mul(i);
+        i += 1;
+    }
+    match exact {
+        Some(value) => Exact::Value(value),
+        None => Exact::Huge(modular, false),
+    }
+}
+
+/// Returns the exact result given by a checked operation, or else the wrapped one.
+fn exact(checked: Option<i128>, wrapped: i128, negative: bool) -> Exact {
+    match checked {
+        Some(value) => Exact::Value(value),
+        None => Exact::Huge(wrapped, negative),
+    }
+}
+
+impl Value for Int {
+    /// Converts the literal, rounding toward zero. [`infer_types`] rejects fractional literals, and
+    /// those too large to be exact.
+    fn from_num(num: f64) -> Self {
+        Self::untyped(num as i128)
+    }
+
+    fn calc_unop(op: UnOp, operand: &Self) -> Result<Self> {
+        let exact = match op {
+            UnOp::Negate => exact(
+                operand.value.checked_neg(),
+                operand.value.wrapping_neg(),
+                false,
+            ),
+            UnOp::Factorial => {
+                if operand.value < 0 {
+                    bail!("factorial of a negative number");
+                }
+                factorial(operand.value)
+            }
+            UnOp::Percent => bail!("percent of an integer"),
+        };
+        let what = match op {
+            UnOp::Negate => format!("-{}", operand),
+            _ => format!("{}{}", operand, op.symbol()),
+        };
+        operand.resolve(exact, &what)
+    }
+
+    fn calc_binop(op: BinOp, lhs: &Self, rhs: &Self) -> Result<Self> {
+        let (a, b) = (lhs.value, rhs.value);
+        // The exponent of a power may have any type, like that of Rust's `pow`.
+        let int = match (lhs.ty, rhs.ty) {
+            (Some(l), Some(r)) if l != r && op != BinOp::Power => {
+                bail!("mismatched integer types {} and {}", l, r)
+            }
+            (Some(_), _) | (None, None) => *lhs,
+            (None, Some(_)) => *rhs,
+        };
+        let int = Int {
+            overflowed: lhs.overflowed || rhs.overflowed,
+            ..int
+        };
+        let exact = match op {
+            BinOp::Add => exact(a.checked_add(b), a.wrapping_add(b), a < 0),
+            BinOp::Subtract => exact(a.checked_sub(b), a.wrapping_sub(b), a < 0),
+            BinOp::Multiply => exact(a.checked_mul(b), a.wrapping_mul(b), (a < 0) != (b < 0)),
+            BinOp::Divide => {
+                if b == 0 {
+                    bail!("cannot divide 0");
+                }
+                exact(a.checked_div(b), a.wrapping_div(b), false)
+            }
+            BinOp::Power => match u128::try_from(b) {
+                Ok(b) => pow(a, b),
+                Err(_) => bail!("negative exponent {}", rhs),
+            },
+            BinOp::PlusMinus => bail!("uncertainties need an uncertain value type"),
+        };
+        int.resolve(exact, &format!("{} {} {}", lhs, op.symbol(), rhs))
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        Some(self.value as f64)
+    }
+
+    fn warning(&self) -> Option<String> {
+        if !self.overflowed {
+            return None;
+        }
+        let ty = self.ty?;
+        Some(match self.overflow {
+            Overflow::Saturating => format!("{} arithmetic overflowed and saturated", ty),
+            _ => format!("{} arithmetic overflowed and wrapped around", ty),
+        })
+    }
+}
+
+/// Prints the integer as a literal with its type as the suffix, e.g. `255u8`.
+impl fmt::Display for Int {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)?;
+        if let Some(ty) = self.ty {
+            write!(f, "{}", ty)?;
+        }
+        Ok(())
+    }
+}
+
+/// Registers the casts `u8(x)`, ..., `isize(x)`, which create integers with the given overflow
+/// mode.
+pub fn register(context: &mut Context<Int>, overflow: Overflow) -> &mut Context<Int> {
+    for ty in IntType::ALL {
+        let _ = context.register_fn(ty.name(), 1, move |args| args[0].cast(ty, overflow));
+    }
+    context
+}
+
+/// Returns the type of the expression if it is known before calculating it, i.e., if it has a
+/// literal with a suffix, a cast, or a variable of a fixed-width type.
+fn static_type(expression: &Expression, context: &Context<Int>) -> Option<IntType> {
+    match expression {
+        Expression::Num(_) => None,
+        Expression::Variable(var) => context.get_var(var)?.ty,
+        Expression::Call { name, .. } => IntType::from_name(name),
+        Expression::BinOp {
+            op: BinOp::Power,
+            lhs,
+            ..
+        } => static_type(lhs, context),
+        Expression::BinOp { lhs, rhs, .. } => {
+            static_type(lhs, context).or_else(|| static_type(rhs, context))
+        }
+        Expression::UnOp { expr, .. } => static_type(expr, context),
+        Expression::List(_) | Expression::Lambda { .. } => None,
+    }
+}
+
+/// Fails if the literal is not an integer that it represents exactly. Literals are parsed as `f64`,
+/// which rounds integers from 2^53 on.
+pub(crate) fn check_integer_literal(num: f64) -> Result<()> {
+    if num.fract() != 0_f64 {
+        bail!("{} is not an integer literal", num);
+    }
+    if num.abs() >= 2_f64.powi(53) {
+        bail!(
+            "integer literal {} is not exact, as literals from 2^53 on are rounded; compute it instead, e.g. `(2 ^ 63 - 1) * 2 + 1`",
+            num
+        );
+    }
+    Ok(())
+}
+
+/// Gives each literal without a suffix a type, by casting it: that of the operand it is combined
+/// with, like Rust infers it, or else `default`. Exponents default to `u32`, like those of Rust's
+/// `pow`.
+///
+/// Fails if a literal is not an integer, or is not exact since its magnitude is at least 2^53.
+pub fn infer_types(
+    expression: &Expression,
+    default: IntType,
+    context: &Context<Int>,
+) -> Result<Expression> {
+    let infer = |expression: &Expression, default: IntType| {
+        let ty = static_type(expression, context).unwrap_or(default);
+        infer_types(expression, ty, context)
+    };
+    Ok(match expression {
+        Expression::Num(num) => {
+            check_integer_literal(*num)?;
+            Expression::Call {
+                name: default.name().to_string(),
+                args: vec![expression.clone()],
+            }
+        }
+        // A literal with a suffix.
+        Expression::Call { name, args }
+            if IntType::from_name(name).is_some()
+                && matches!(args.as_slice(), [Expression::Num(_)]) =>
+        {
+            if let [Expression::Num(num)] = args.as_slice() {
+                check_integer_literal(*num)?;
+            }
+            expression.clone()
+        }
+        Expression::Variable(_) => expression.clone(),
+        Expression::BinOp { op, lhs, rhs } => {
+            let (lhs, rhs) = if *op == BinOp::Power {
+                (infer(lhs, default)?, infer(rhs, IntType::U32)?)
+            } else {
+                let ty = static_type(expression, context).unwrap_or(default);
+                (
+                    infer_types(lhs, ty, context)?,
+                    infer_types(rhs, ty, context)?,
+                )
+            };
+            Expression::BinOp {
+                op: *op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            }
+        }
+        Expression::UnOp { op, expr } => Expression::UnOp {
+            op: *op,
+            expr: Box::new(infer_types(expr, default, context)?),
+        },
+        Expression::Call { name, args } => Expression::Call {
+            name: name.clone(),
+            args: args
+                .iter()
+                .map(|arg| infer(arg, default))
+                .collect::<Result<_>>()?,
+        },
+        Expression::List(elements) => Expression::List(
+            elements
+                .iter()
+                .map(|element| infer(element, default))
+                .collect::<Result<_>>()?,
+        ),
+        Expression::Lambda { params, body } => Expression::Lambda {
+            params: params.clone(),
+            body: Box::new(infer(body, default)?),
+        },
+    })
+}
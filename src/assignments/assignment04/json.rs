@@ -0,0 +1,253 @@
+//! Minimal JSON values for the language server's JSON-RPC messages.
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use anyhow::*;
+
+/// JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool(bool),
+    /// Number.
+    Number(f64),
+    /// String.
+    String(String),
+    /// Array.
+    Array(Vec<Json>),
+    /// Object, with its members in order.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Creates an object from its members.
+    pub(crate) fn object(members: Vec<(&str, Json)>) -> Self {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Returns the member of the object.
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Returns the member at the given path of keys.
+    pub(crate) fn pointer(&self, path: &[&str]) -> Option<&Json> {
+        path.iter().try_fold(self, |json, key| json.get(key))
+    }
+
+    /// Returns the string, if it is one.
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as a natural number, if it is one.
+    pub(crate) fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(num) if *num >= 0_f64 && num.fract() == 0_f64 => Some(*num as usize),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of the array, if it is one.
+    pub(crate) fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Parses the JSON text.
+    pub(crate) fn parse(text: &str) -> Result<Self> {
+        let mut chars = text.chars().peekable();
+        let json = parse_value(&mut chars)?;
+        skip_whitespace(&mut chars);
+        if let Some(ch) = chars.next() {
+            bail!("unexpected `{}` after JSON value", ch);
+        }
+        Ok(json)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(string: &str) -> Self {
+        Json::String(string.to_string())
+    }
+}
+
+impl From<usize> for Json {
+    fn from(num: usize) -> Self {
+        Json::Number(num as f64)
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars<'_>>) {
+    while chars.next_if(|ch| ch.is_ascii_whitespace()).is_some() {}
+}
+
+fn expect(chars: &mut Peekable<Chars<'_>>, expected: &str) -> Result<()> {
+    for ch in expected.chars() {
+        if chars.next() != Some(ch) {
+            bail!("expected `{}`", expected);
+        }
+    }
+    Ok(())
+}
+
+fn parse_value(chars: &mut Peekable<Chars<'_>>) -> Result<Json> {
+    skip_whitespace(chars);
+    match chars.peek() {
+        Some('n') => expect(chars, "null").map(|_| Json::Null),
+        Some('t') => expect(chars, "true").map(|_| Json::Bool(true)),
+        Some('f') => expect(chars, "false").map(|_| Json::Bool(false)),
+        Some('"') => parse_string(chars).map(Json::String),
+        Some('[') => {
+            let _ = chars.next();
+            let mut elements = Vec::new();
+            skip_whitespace(chars);
+            if chars.next_if_eq(&']').is_some() {
+                return Ok(Json::Array(elements));
+            }
+            loop {
+                elements.push(parse_value(chars)?);
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some(']') => return Ok(Json::Array(elements)),
+                    _ => bail!("expected `,` or `]`"),
+                }
+            }
+        }
+        Some('{') => {
+            let _ = chars.next();
+            let mut members = Vec::new();
+            skip_whitespace(chars);
+            if chars.next_if_eq(&'}').is_some() {
+                return Ok(Json::Object(members));
+            }
+            loop {
+                skip_whitespace(chars);
+                let key = parse_string(chars)?;
+                skip_whitespace(chars);
+                expect(chars, ":")?;
+                members.push((key, parse_value(chars)?));
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some('}') => return Ok(Json::Object(members)),
+                    _ => bail!("expected `,` or `}}`"),
+                }
+            }
+        }
+        Some(ch) if *ch == '-' || ch.is_ascii_digit() => {
+            let mut number = String::new();
+            while let Some(ch) = chars.next_if(|ch| ch.is_ascii_digit() || "+-.eE".contains(*ch)) {
+                number.push(ch);
+            }
+            Ok(Json::Number(number.parse()?))
+        }
+        _ => bail!("expected a JSON value"),
+    }
+}
+
+fn parse_hex4(chars: &mut Peekable<Chars<'_>>) -> Result<u32> {
+    let hex = chars.take(4).collect::<String>();
+    Ok(u32::from_str_radix(&hex, 16)?)
+}
+
+fn parse_string(chars: &mut Peekable<Chars<'_>>) -> Result<String> {
+    expect(chars, "\"")?;
+    let mut string = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(string),
+            Some('\\') => match chars.next() {
+                Some('"') => string.push('"'),
+                Some('\\') => string.push('\\'),
+                Some('/') => string.push('/'),
+                Some('b') => string.push('\u{8}'),
+                Some('f') => string.push('\u{c}'),
+                Some('n') => string.push('\n'),
+                Some('r') => string.push('\r'),
+                Some('t') => string.push('\t'),
+                Some('u') => {
+                    let mut code = parse_hex4(chars)?;
+                    if (0xD800..0xDC00).contains(&code) {
+                        // Surrogate pair.
+                        expect(chars, "\\u")?;
+                        let low = parse_hex4(chars)?;
+                        code = 0x10000 + ((code - 0xD800) << 10) + low.wrapping_sub(0xDC00);
+                    }
+                    string.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                _ => bail!("invalid escape in JSON string"),
+            },
+            Some(ch) => string.push(ch),
+            None => bail!("unterminated JSON string"),
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, string: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for ch in string.chars() {
+        match ch {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            ch if (ch as u32) < 0x20 => write!(f, "\\u{:04x}", ch as u32)?,
+            ch => write!(f, "{}", ch)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Serializes the value as compact JSON text.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(num) if num.is_finite() => write!(f, "{}", num),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(string) => write_string(f, string),
+            Json::Array(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Resource limits for evaluating untrusted input.
+
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use super::syntax::Expression;
+
+/// Resource limits enforced by [`parse_command_with_limits`](super::parser::parse_command_with_limits)
+/// and [`Context`](super::context::Context).
+///
+/// The defaults bound the length and the nesting depth so that untrusted input cannot overflow the
+/// stack, and leave the integer size and wall time unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum length of the source text, in bytes.
+    pub max_length: usize,
+    /// Maximum nesting depth of an expression, also while evaluating the bodies of the lambdas
+    /// it calls.
+    pub max_depth: usize,
+    /// Maximum number of nodes of an expression.
+    pub max_nodes: usize,
+    /// Maximum number of evaluation steps for a single expression.
+    pub max_steps: usize,
+    /// Maximum size of the integer part of any intermediate value, in bits.
+    pub max_integer_bits: Option<u32>,
+    /// Maximum wall time for evaluating a single expression.
+    pub max_time: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_length: 256 * 1024,
+            max_depth: 256,
+            max_nodes: 65_536,
+            max_steps: 1_000_000,
+            max_integer_bits: None,
+            max_time: None,
+        }
+    }
+}
+
+/// Error raised when the input exceeds one of the [`Limits`].
+///
+/// It is returned inside `anyhow::Error`; use `error.downcast_ref::<LimitExceeded>()` to tell it
+/// apart from syntax and evaluation errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+    /// [`Limits::max_length`] is exceeded.
+    Length(usize),
+    /// [`Limits::max_depth`] is exceeded.
+    Depth(usize),
+    /// [`Limits::max_nodes`] is exceeded.
+    Nodes(usize),
+    /// [`Limits::max_steps`] is exceeded.
+    Steps(usize),
+    /// [`Limits::max_integer_bits`] is exceeded.
+    IntegerBits(u32),
+    /// [`Limits::max_time`] is exceeded.
+    Time(Duration),
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Length(limit) => write!(f, "source is longer than {} bytes", limit),
+            Self::Depth(limit) => write!(f, "expression is nested deeper than {} levels", limit),
+            Self::Nodes(limit) => write!(f, "expression has more than {} nodes", limit),
+            Self::Steps(limit) => write!(f, "evaluation takes more than {} steps", limit),
+            Self::IntegerBits(limit) => write!(f, "value is larger than {} bits", limit),
+            Self::Time(limit) => write!(f, "evaluation takes longer than {:?}", limit),
+        }
+    }
+}
+
+impl Error for LimitExceeded {}
+
+impl Limits {
+    /// Checks the length of the source text and upper bounds of the nesting depth of its
+    /// expression.
+    ///
+    /// This runs before parsing so that long or deeply nested input is rejected before the parser
+    /// recurses into it. Besides brackets, every operator may nest the expression one level
+    /// deeper, e.g. `1 - 1 - 1`, `2 ^ 2 ^ 2`, `- - 1`, `1!!` and `x -> y -> 1`, so the depth of
+    /// each bracketed argument is bounded by its operators plus the depth of its deepest bracket.
+    pub fn check_source(&self, line: &str) -> Result<()> {
+        if line.len() > self.max_length {
+            return Err(LimitExceeded::Length(self.max_length).into());
+        }
+
+        let exceeded = || Err(LimitExceeded::Depth(self.max_depth).into());
+        let mut open = vec![Nesting::default()];
+        let mut previous = [' ', ' '];
+        for ch in line.chars() {
+            let exponent = matches!(previous, [digit, 'e' | 'E'] if digit.is_ascii_digit());
+            match ch {
+                '(' | '[' => {
+                    if open.len() > self.max_depth {
+                        return exceeded();
+                    }
+                    // Calls and lists are nodes, but groups are not.
+                    let name = previous[1].is_ascii_alphanumeric() || previous[1] == '_';
+                    open.push(Nesting {
+                        node: ch == '[' || name,
+                        ..Nesting::default()
+                    });
+                }
+                ')' | ']' if open.len() > 1 => {
+                    let inner = open.pop().expect("a bracket is open");
+                    let outer = open
+                        .last_mut()
+                        .expect("the outermost level is never closed");
+                    outer.deepest = outer.deepest.max(inner.depth() + usize::from(inner.node));
+                }
+                ',' => {
+                    let current = open
+                        .last_mut()
+                        .expect("the outermost level is never closed");
+                    current.widest = current.depth();
+                    current.operators = 0;
+                    current.deepest = 0;
+                }
+                // The sign of an exponent, e.g. `1e-5`, is not an operator.
+                '+' | '-' if exponent => {}
+                '+' | '-' | '*' | '/' | '^' | '!' | '%' | '±' => {
+                    open.last_mut()
+                        .expect("the outermost level is never closed")
+                        .operators += 1
+                }
+                _ => {}
+            }
+            if !ch.is_whitespace() {
+                previous = [previous[1], ch];
+            }
+        }
+
+        // Brackets left open still make the parser recurse before it fails.
+        let mut depth = 0;
+        while let Some(mut nesting) = open.pop() {
+            nesting.deepest = nesting.deepest.max(depth);
+            depth = nesting.depth() + usize::from(nesting.node);
+        }
+        if depth > self.max_depth {
+            return exceeded();
+        }
+        Ok(())
+    }
+
+    /// Checks the depth and the number of nodes of the expression.
+    pub fn check_expression(&self, expression: &Expression) -> Result<()> {
+        // Walks the tree with an explicit stack, as the tree may be too deep to recurse into.
+        let mut nodes = 0_usize;
+        let mut stack = vec![(expression, 1_usize)];
+        while let Some((expression, depth)) = stack.pop() {
+            nodes += 1;
+            if nodes > self.max_nodes {
+                return Err(LimitExceeded::Nodes(self.max_nodes).into());
+            }
+            if depth > self.max_depth {
+                return Err(LimitExceeded::Depth(self.max_depth).into());
+            }
+            stack.extend(expression.children().into_iter().map(|e| (e, depth + 1)));
+        }
+        Ok(())
+    }
+
+    /// Checks the size of the value's integer part.
+    pub(crate) fn check_integer(&self, value: Option<f64>) -> Result<()> {
+        if let (Some(limit), Some(value)) = (self.max_integer_bits, value) {
+            if value.abs().log2() >= limit as f64 {
+                return Err(LimitExceeded::IntegerBits(limit).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the estimated size of `base ^ exponent` before computing it.
+    pub(crate) fn check_power(&self, base: Option<f64>, exponent: Option<f64>) -> Result<()> {
+        if let (Some(limit), Some(base), Some(exponent)) = (self.max_integer_bits, base, exponent) {
+            if exponent * base.abs().log2() >= limit as f64 {
+                return Err(LimitExceeded::IntegerBits(limit).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Nesting of the source text within a pair of brackets, for [`Limits::check_source`].
+#[derive(Debug, Default, Clone, Copy)]
+struct Nesting {
+    /// Whether the brackets make a node, i.e., a call or a list.
+    node: bool,
+    /// Number of operators in the current argument.
+    operators: usize,
+    /// Depth of the deepest bracket in the current argument.
+    deepest: usize,
+    /// Depth of the deepest argument before the current one.
+    widest: usize,
+}
+
+impl Nesting {
+    /// Returns an upper bound of the depth of the arguments so far.
+    fn depth(&self) -> usize {
+        self.widest.max(self.operators + self.deepest)
+    }
+}
+
+/// Evaluation budget of a single expression.
+#[derive(Debug)]
+pub(crate) struct Budget {
+    steps: usize,
+    max_steps: usize,
+    depth: usize,
+    max_depth: usize,
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl Budget {
+    /// Creates a new budget starting now.
+    pub(crate) fn new(limits: &Limits) -> Self {
+        Self {
+            steps: 0,
+            max_steps: limits.max_steps,
+            depth: 0,
+            max_depth: limits.max_depth,
+            deadline: limits.max_time.map(|limit| (Instant::now() + limit, limit)),
+        }
+    }
+
+    /// Consumes an evaluation step.
+    pub(crate) fn step(&mut self) -> Result<()> {
+        self.steps += 1;
+        if self.steps > self.max_steps {
+            return Err(LimitExceeded::Steps(self.max_steps).into());
+        }
+        if let Some((deadline, limit)) = self.deadline {
+            if Instant::now() >= deadline {
+                return Err(LimitExceeded::Time(limit).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Enters a subexpression. Lambdas calling lambdas nest their bodies, so the depth is checked
+    /// during evaluation as well.
+    pub(crate) fn enter(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.depth > self.max_depth {
+            return Err(LimitExceeded::Depth(self.max_depth).into());
+        }
+        Ok(())
+    }
+
+    /// Leaves the subexpression entered last.
+    pub(crate) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+}
@@ -0,0 +1,488 @@
+//! Language server for calculator files.
+//!
+//! The server speaks the [Language Server Protocol](https://microsoft.github.io/language-server-protocol/)
+//! (JSON-RPC messages framed by `Content-Length` headers) over any reader and writer, e.g. stdin
+//! and stdout. Each document is evaluated line by line as `calc` evaluates a file, and the server
+//! provides:
+//!
+//! - diagnostics for syntax and evaluation errors;
+//! - hover showing the value of the variable under the cursor;
+//! - go-to-definition, jumping to the assignment that gave the variable its value; and
+//! - completion of variable and function names.
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::result::Result::Ok;
+
+use anyhow::*;
+
+use super::calendar;
+use super::context::{Context, Dynamic};
+use super::finance;
+use super::json::Json;
+use super::parser;
+use super::session::{self, Outcome};
+use super::statistics;
+
+/// `DiagnosticSeverity.Error`.
+const SEVERITY_ERROR: usize = 1;
+/// `CompletionItemKind.Function`.
+const KIND_FUNCTION: usize = 3;
+/// `CompletionItemKind.Variable`.
+const KIND_VARIABLE: usize = 6;
+/// JSON-RPC error code for unparsable messages.
+const PARSE_ERROR: f64 = -32700.0;
+/// JSON-RPC error code for unknown methods.
+const METHOD_NOT_FOUND: f64 = -32601.0;
+/// JSON-RPC error code for malformed parameters.
+const INVALID_PARAMS: f64 = -32602.0;
+
+/// Language server.
+#[derive(Debug, Clone)]
+pub struct Server {
+    /// Context every document is evaluated in.
+    context: Context<Dynamic>,
+    /// Text of each open document, by URI.
+    documents: HashMap<String, String>,
+    /// Whether the client asked the server to exit.
+    exit: bool,
+}
+
+/// Assignment of a variable in a document.
+#[derive(Debug)]
+struct Definition {
+    variable: String,
+    line: usize,
+    /// Columns of the assigned name (or the anonymous expression), in UTF-16 code units.
+    start: usize,
+    end: usize,
+}
+
+/// Result of evaluating a document.
+#[derive(Debug)]
+struct Analysis {
+    context: Context<Dynamic>,
+    diagnostics: Vec<Json>,
+    definitions: Vec<Definition>,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        let mut context = Context::default();
+        let _ = statistics::register(&mut context);
+        let _ = calendar::register(&mut context);
+        let _ = finance::register(&mut context);
+        Self::new(context)
+    }
+}
+
+impl Server {
+    /// Creates a server evaluating documents in the given context, so that its variables and
+    /// functions are available to every document.
+    pub fn new(context: Context<Dynamic>) -> Self {
+        Self {
+            context,
+            documents: HashMap::new(),
+            exit: false,
+        }
+    }
+
+    /// Serves the client until it sends `exit` or closes the input.
+    pub fn serve<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W) -> Result<()> {
+        while let Some(message) = read_message(&mut input)? {
+            let responses = match Json::parse(&message) {
+                Ok(message) => self.handle(&message),
+                Err(error) => vec![error_response(Json::Null, PARSE_ERROR, &error)],
+            };
+            for response in responses {
+                write_message(&mut output, &response)?;
+            }
+            if self.exit {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the message, returning the messages to send back.
+    fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let id = match message.get("id") {
+            Some(id) => id.clone(),
+            None => return self.notify(method, params).into_iter().collect(),
+        };
+        let response = match self.request(method, params) {
+            Ok(Some(result)) => Json::object(vec![
+                ("jsonrpc", "2.0".into()),
+                ("id", id),
+                ("result", result),
+            ]),
+            Ok(None) => error_response(id, METHOD_NOT_FOUND, &anyhow!("unknown method {}", method)),
+            Err(error) => error_response(id, INVALID_PARAMS, &error),
+        };
+        vec![response]
+    }
+
+    /// Handles the notification, returning the diagnostics to publish.
+    fn notify(&mut self, method: &str, params: &Json) -> Option<Json> {
+        let uri = params
+            .pointer(&["textDocument", "uri"])
+            .and_then(Json::as_str)
+            .map(str::to_string);
+        match method {
+            "exit" => {
+                self.exit = true;
+                None
+            }
+            "textDocument/didOpen" => {
+                let uri = uri?;
+                let text = params.pointer(&["textDocument", "text"])?.as_str()?;
+                let _unused = self.documents.insert(uri.clone(), text.to_string());
+                Some(self.publish(&uri))
+            }
+            "textDocument/didChange" => {
+                // The server asks for full synchronization, so the last change is the whole text.
+                let uri = uri?;
+                let changes = params.get("contentChanges")?.as_array()?;
+                let text = changes.last()?.get("text")?.as_str()?;
+                let _unused = self.documents.insert(uri.clone(), text.to_string());
+                Some(self.publish(&uri))
+            }
+            "textDocument/didClose" => {
+                let uri = uri?;
+                let _unused = self.documents.remove(&uri);
+                Some(self.publish(&uri))
+            }
+            _ => None,
+        }
+    }
+
+    /// Handles the request, returning its result, or `None` if the method is unknown.
+    fn request(&mut self, method: &str, params: &Json) -> Result<Option<Json>> {
+        let result = match method {
+            "initialize" => Json::object(vec![
+                (
+                    "capabilities",
+                    Json::object(vec![
+                        ("textDocumentSync", 1_usize.into()),
+                        ("hoverProvider", Json::Bool(true)),
+                        ("definitionProvider", Json::Bool(true)),
+                        ("completionProvider", Json::object(vec![])),
+                    ]),
+                ),
+                (
+                    "serverInfo",
+                    Json::object(vec![("name", "calc-lsp".into())]),
+                ),
+            ]),
+            "shutdown" => Json::Null,
+            "textDocument/hover" => self.hover(params)?,
+            "textDocument/definition" => self.definition(params)?,
+            "textDocument/completion" => self.completion(params)?,
+            _ => return Ok(None),
+        };
+        Ok(Some(result))
+    }
+
+    /// Returns the `textDocument/publishDiagnostics` notification for the document.
+    fn publish(&self, uri: &str) -> Json {
+        let diagnostics = match self.documents.get(uri) {
+            Some(text) => self.analyze(text, usize::MAX).diagnostics,
+            None => Vec::new(),
+        };
+        Json::object(vec![
+            ("jsonrpc", "2.0".into()),
+            ("method", "textDocument/publishDiagnostics".into()),
+            (
+                "params",
+                Json::object(vec![
+                    ("uri", uri.into()),
+                    ("diagnostics", Json::Array(diagnostics)),
+                ]),
+            ),
+        ])
+    }
+
+    /// Returns the document and the position of `TextDocumentPositionParams`.
+    fn position<'s>(&'s self, params: &Json) -> Result<(&'s str, usize, usize)> {
+        let uri = params
+            .pointer(&["textDocument", "uri"])
+            .and_then(Json::as_str)
+            .ok_or_else(|| anyhow!("missing document"))?;
+        let text = self
+            .documents
+            .get(uri)
+            .ok_or_else(|| anyhow!("document {} is not open", uri))?;
+        let line = params
+            .pointer(&["position", "line"])
+            .and_then(Json::as_usize);
+        let character = params
+            .pointer(&["position", "character"])
+            .and_then(Json::as_usize);
+        match (line, character) {
+            (Some(line), Some(character)) => Ok((text, line, character)),
+            _ => bail!("missing position"),
+        }
+    }
+
+    /// Returns the variable at the position, and its assignment if the position is on it.
+    fn variable_at(&self, params: &Json) -> Result<Option<(String, Analysis, bool)>> {
+        let (text, line, character) = self.position(params)?;
+        let variable = match text.lines().nth(line).and_then(|l| word_at(l, character)) {
+            Some(variable) => variable,
+            None => return Ok(None),
+        };
+        let analysis = self.analyze(text, line + 1);
+        let assigned = analysis.definitions.last().map_or(false, |definition| {
+            definition.line == line
+                && definition.variable == variable
+                && (definition.start..=definition.end).contains(&character)
+        });
+        Ok(Some((variable, analysis, assigned)))
+    }
+
+    fn hover(&self, params: &Json) -> Result<Json> {
+        let (text, line, _) = self.position(params)?;
+        let (variable, analysis, assigned) = match self.variable_at(params)? {
+            Some(found) => found,
+            None => return Ok(Json::Null),
+        };
+        // Elsewhere on the line, the variable still has the value from before the line.
+        let context = if assigned {
+            analysis.context
+        } else {
+            self.analyze(text, line).context
+        };
+        let contents = match context.get_var(&variable) {
+            Some(value) => format!("{} = {}", variable, value),
+            None => match context.functions().find(|(name, _)| *name == variable) {
+                Some((name, arity)) => format!("{}: function of {} arguments", name, arity),
+                None => return Ok(Json::Null),
+            },
+        };
+        Ok(Json::object(vec![(
+            "contents",
+            Json::object(vec![
+                ("kind", "plaintext".into()),
+                ("value", contents.as_str().into()),
+            ]),
+        )]))
+    }
+
+    fn definition(&self, params: &Json) -> Result<Json> {
+        let (_, line, _) = self.position(params)?;
+        let uri = params
+            .pointer(&["textDocument", "uri"])
+            .cloned()
+            .unwrap_or(Json::Null);
+        let (variable, analysis, assigned) = match self.variable_at(params)? {
+            Some(found) => found,
+            None => return Ok(Json::Null),
+        };
+        let definition = analysis
+            .definitions
+            .iter()
+            .rev()
+            .filter(|definition| assigned || definition.line < line)
+            .find(|definition| definition.variable == variable);
+        Ok(match definition {
+            Some(definition) => Json::object(vec![
+                ("uri", uri),
+                (
+                    "range",
+                    range(definition.line, definition.start, definition.end),
+                ),
+            ]),
+            None => Json::Null,
+        })
+    }
+
+    fn completion(&self, params: &Json) -> Result<Json> {
+        let (text, line, _) = self.position(params)?;
+        let context = self.analyze(text, line).context;
+        let mut items = context
+            .variables()
+            .map(|(name, value)| (name.to_string(), KIND_VARIABLE, value.to_string()))
+            .chain(context.functions().map(|(name, arity)| {
+                let parameters = vec!["_"; arity].join(", ");
+                (
+                    name.to_string(),
+                    KIND_FUNCTION,
+                    format!("{}({})", name, parameters),
+                )
+            }))
+            .collect::<Vec<_>>();
+        items.sort();
+        Ok(Json::Array(
+            items
+                .into_iter()
+                .map(|(label, kind, detail)| {
+                    Json::object(vec![
+                        ("label", label.as_str().into()),
+                        ("kind", kind.into()),
+                        ("detail", detail.as_str().into()),
+                    ])
+                })
+                .collect(),
+        ))
+    }
+
+    /// Evaluates the first `lines` lines of the document.
+    fn analyze(&self, text: &str, lines: usize) -> Analysis {
+        let mut context = self.context.clone();
+        let mut diagnostics = Vec::new();
+        let mut definitions = Vec::new();
+        for (number, line) in text.lines().enumerate().take(lines) {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let start = utf16_len(&line[..line.len() - line.trim_start().len()]);
+            let end = utf16_len(line.trim_end());
+
+            // Switching between modular and ordinary arithmetic with `:mod` is left to `calc`.
+            match session::run_line(&mut context, line, |expression, _| Ok(expression.clone())) {
+                Ok(Outcome::Calculated {
+                    command, variable, ..
+                }) => {
+                    let (start, end) = match &command.variable {
+                        Some(name) => {
+                            let offset = line.find(name.as_str()).unwrap_or(0);
+                            let start = utf16_len(&line[..offset]);
+                            (start, start + utf16_len(name))
+                        }
+                        None => (start, end),
+                    };
+                    definitions.push(Definition {
+                        variable,
+                        line: number,
+                        start,
+                        end,
+                    });
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    let column = parser::error_column(&error)
+                        .map_or(start, |column| start + utf16_len_of_chars(trimmed, column));
+                    diagnostics.push(diagnostic(number, column, end.max(column), &error));
+                }
+            }
+        }
+        Analysis {
+            context,
+            diagnostics,
+            definitions,
+        }
+    }
+}
+
+/// Serves the client over the reader and writer with the default context.
+pub fn serve<R: BufRead, W: Write>(input: R, output: W) -> Result<()> {
+    Server::default().serve(input, output)
+}
+
+/// Reads the content of the next message, or `None` at the end of the input.
+fn read_message<R: BufRead>(input: &mut R) -> Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+    let length = length.ok_or_else(|| anyhow!("message without Content-Length"))?;
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+    Ok(Some(String::from_utf8(content)?))
+}
+
+/// Writes the message with its header.
+fn write_message<W: Write>(output: &mut W, message: &Json) -> Result<()> {
+    let content = message.to_string();
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()?;
+    Ok(())
+}
+
+fn error_response(id: Json, code: f64, error: &Error) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        (
+            "error",
+            Json::object(vec![
+                ("code", Json::Number(code)),
+                ("message", error.to_string().as_str().into()),
+            ]),
+        ),
+    ])
+}
+
+fn range(line: usize, start: usize, end: usize) -> Json {
+    let position = |character: usize| {
+        Json::object(vec![("line", line.into()), ("character", character.into())])
+    };
+    Json::object(vec![("start", position(start)), ("end", position(end))])
+}
+
+fn diagnostic(line: usize, start: usize, end: usize, error: &Error) -> Json {
+    Json::object(vec![
+        ("range", range(line, start, end)),
+        ("severity", SEVERITY_ERROR.into()),
+        ("source", "calc".into()),
+        ("message", error.to_string().as_str().into()),
+    ])
+}
+
+/// Returns the length of the text in UTF-16 code units, which LSP positions count in.
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Returns the length of the first `chars` characters of the line in UTF-16 code units.
+fn utf16_len_of_chars(line: &str, chars: usize) -> usize {
+    line.chars().take(chars).map(char::len_utf16).sum()
+}
+
+/// Returns the identifier at the column (in UTF-16 code units) of the line.
+fn word_at(line: &str, character: usize) -> Option<String> {
+    let is_word = |ch: char| ch.is_ascii_alphanumeric() || ch == '_' || ch == '$';
+    let mut column = 0;
+    let mut start = 0;
+    let mut word = String::new();
+    for ch in line.chars() {
+        if is_word(ch) {
+            if word.is_empty() {
+                start = column;
+            }
+            word.push(ch);
+        } else if !word.is_empty() {
+            if (start..=column).contains(&character) {
+                break;
+            }
+            word.clear();
+        }
+        column += ch.len_utf16();
+    }
+    let first = word.chars().next()?;
+    if (start..=column).contains(&character) && (first == '$' || first.is_ascii_alphabetic()) {
+        Some(word)
+    } else {
+        None
+    }
+}
@@ -15,6 +15,23 @@
 //! See `assignment04_grade.rs` and `/scripts/grade-04.sh` for the test script.
 //! Run `/scripts/prepare-submissions.sh` and submit `/target/assignment04.zip` to <https://gg.kaist.ac.kr>.
 
+pub mod arbitrary;
+pub mod calendar;
+pub mod compile;
 pub mod context;
+pub mod dag;
+pub mod finance;
+pub mod integer;
+pub(crate) mod json;
+pub mod limits;
+pub mod lsp;
+pub mod modular;
 pub mod parser;
+pub mod plot;
+pub mod polynomial;
+pub mod render;
+pub mod session;
+pub mod sheet;
+pub mod statistics;
 pub mod syntax;
+pub mod uncertain;
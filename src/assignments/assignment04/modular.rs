@@ -0,0 +1,263 @@
+//! Modular arithmetic.
+//!
+//! In a modular session, every literal is a residue modulo the session's modulus `n`, so that
+//! every operation reduces modulo `n`: dividing multiplies by the modular inverse, and powers are
+//! computed by fast modular exponentiation. Exponents are not residues but exact integers, as
+//! reducing them modulo `n` would change the power.
+
+use std::fmt;
+use std::result::Result::Ok;
+
+use anyhow::*;
+
+use super::context::{Context, Value};
+use super::integer::check_integer_literal;
+use super::syntax::{BinOp, Expression, UnOp};
+use crate::assignments::assignment02::extended_gcd;
+
+/// Largest argument of a modular factorial, which is computed by multiplying.
+const MAX_FACTORIAL: u64 = 1_000_000;
+
+/// Returns the inverse of `a` modulo `n`, if `a` and `n` are coprime.
+pub fn mod_inverse(a: u64, n: u64) -> Option<u64> {
+    let (g, x, _) = extended_gcd(a % n, n);
+    if g != 1 {
+        return None;
+    }
+    Some(x.rem_euclid(n as i128) as u64)
+}
+
+/// Returns `(a * b) mod n`.
+pub fn mod_mul(a: u64, b: u64, n: u64) -> u64 {
+    (a as u128 * b as u128 % n as u128) as u64
+}
+
+/// Returns `base ^ exponent mod n`, by exponentiation by squaring.
+pub fn mod_pow(base: u64, exponent: u128, n: u64) -> u64 {
+    let (mut base, mut exponent, mut result) = (base % n, exponent, 1 % n);
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = mod_mul(result, base, n);
+        }
+        base = mod_mul(base, base, n);
+        exponent /= 2;
+    }
+    result
+}
+
+/// Value in a modular session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modular {
+    /// Exact integer, e.g. an exponent.
+    Int(i128),
+    /// Residue modulo `modulus`.
+    Residue {
+        /// Least non-negative representative.
+        value: u64,
+        /// Modulus.
+        modulus: u64,
+    },
+}
+
+impl Modular {
+    /// Creates the residue of `value` modulo `modulus`, which must be positive.
+    pub fn residue(value: i128, modulus: u64) -> Self {
+        Modular::Residue {
+            value: value.rem_euclid(modulus as i128) as u64,
+            modulus,
+        }
+    }
+
+    /// Returns the integer, or the least non-negative representative of the residue.
+    pub fn value(&self) -> i128 {
+        match self {
+            Modular::Int(value) => *value,
+            Modular::Residue { value, .. } => *value as i128,
+        }
+    }
+
+    /// Returns the modulus, if the value is a residue.
+    pub fn modulus(&self) -> Option<u64> {
+        match self {
+            Modular::Int(_) => None,
+            Modular::Residue { modulus, .. } => Some(*modulus),
+        }
+    }
+}
+
+impl Value for Modular {
+    /// Converts the literal, rounding toward zero. [`with_modulus`] rejects fractional literals, and
+    /// those too large to be exact.
+    fn from_num(num: f64) -> Self {
+        Modular::Int(num as i128)
+    }
+
+    fn calc_unop(op: UnOp, operand: &Self) -> Result<Self> {
+        match (op, *operand) {
+            (UnOp::Negate, Modular::Int(value)) => match value.checked_neg() {
+                Some(value) => Ok(Modular::Int(value)),
+                None => bail!("-{} overflows i128", value),
+            },
+            (UnOp::Negate, Modular::Residue { value, modulus }) => {
+                Ok(Modular::residue(-(value as i128), modulus))
+            }
+            (UnOp::Factorial, Modular::Int(value)) => {
+                if value < 0 {
+                    bail!("factorial of a negative number");
+                }
+                (2..=value)
+                    .try_fold(1_i128, |product, i| product.checked_mul(i))
+                    .map(Modular::Int)
+                    .ok_or_else(|| anyhow!("{}! overflows i128", value))
+            }
+            (UnOp::Factorial, Modular::Residue { value, modulus }) => {
+                if value > MAX_FACTORIAL {
+                    bail!("factorial of more than {}", MAX_FACTORIAL);
+                }
+                let product = (2..=value).fold(1 % modulus, |product, i| {
+                    mod_mul(product, i % modulus, modulus)
+                });
+                Ok(Modular::Residue {
+                    value: product,
+                    modulus,
+                })
+            }
+            (UnOp::Percent, _) => bail!("percent in modular arithmetic"),
+        }
+    }
+
+    fn calc_binop(op: BinOp, lhs: &Self, rhs: &Self) -> Result<Self> {
+        let modulus = match (lhs.modulus(), rhs.modulus()) {
+            (Some(m), Some(n)) if m != n => bail!("residues modulo {} and {}", m, n),
+            (m, n) => m.or(n),
+        };
+        let (a, b) = (lhs.value(), rhs.value());
+        let n = match modulus {
+            Some(n) => n,
+            // Exact integer arithmetic, e.g. on exponents.
+            None => {
+                let value = match op {
+                    BinOp::Add => a.checked_add(b),
+                    BinOp::Subtract => a.checked_sub(b),
+                    BinOp::Multiply => a.checked_mul(b),
+                    BinOp::Divide if b != 0 && a % b == 0 => Some(a / b),
+                    BinOp::Divide => bail!("{} is not divisible by {}", a, b),
+                    BinOp::Power => match u32::try_from(b) {
+                        Ok(b) => a.checked_pow(b),
+                        Err(_) => bail!("integer power with exponent {}", b),
+                    },
+                    BinOp::PlusMinus => bail!("uncertainties in modular arithmetic"),
+                };
+                return value
+                    .map(Modular::Int)
+                    .ok_or_else(|| anyhow!("{} {} {} overflows i128", a, op.symbol(), b));
+            }
+        };
+        let (x, y) = (
+            Modular::residue(a, n).value() as u64,
+            Modular::residue(b, n).value() as u64,
+        );
+        let value = match op {
+            BinOp::Add => ((x as u128 + y as u128) % n as u128) as u64,
+            BinOp::Subtract => ((x as u128 + n as u128 - y as u128) % n as u128) as u64,
+            BinOp::Multiply => mod_mul(x, y, n),
+            BinOp::Divide => match mod_inverse(y, n) {
+                Some(inverse) => mod_mul(x, inverse, n),
+                None => bail!("{} is not invertible modulo {}", y, n),
+            },
+            // The exponent is the integer, or the representative of the residue.
+            BinOp::Power if b >= 0 => mod_pow(x, b as u128, n),
+            BinOp::Power => match mod_inverse(x, n) {
+                Some(inverse) => mod_pow(inverse, b.unsigned_abs(), n),
+                None => bail!("{} is not invertible modulo {}", x, n),
+            },
+            BinOp::PlusMinus => bail!("uncertainties in modular arithmetic"),
+        };
+        Ok(Modular::Residue { value, modulus: n })
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        Some(self.value() as f64)
+    }
+}
+
+/// Prints the residue with its modulus, e.g. `3 (mod 7)`.
+impl fmt::Display for Modular {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Modular::Int(value) => write!(f, "{}", value),
+            Modular::Residue { value, modulus } => write!(f, "{} (mod {})", value, modulus),
+        }
+    }
+}
+
+/// Registers `mod(a, n)`, the residue of `a` modulo `n`.
+pub fn register(context: &mut Context<Modular>) -> &mut Context<Modular> {
+    context.register_fn("mod", 2, |args| match args[1].value() {
+        n if n > 0 && n <= u64::MAX as i128 => Ok(Modular::residue(args[0].value(), n as u64)),
+        n => bail!("modulus {} is not a positive 64-bit integer", n),
+    })
+}
+
+/// Fails if a literal in the expression is not an integer that it represents exactly.
+fn check_integers(expression: &Expression) -> Result<()> {
+    if let Expression::Num(num) = expression {
+        check_integer_literal(*num)?;
+    }
+    expression
+        .children()
+        .into_iter()
+        .try_for_each(check_integers)
+}
+
+/// Makes the literals residues modulo `modulus`, by calling `mod`, except in exponents.
+///
+/// Fails if a literal, including one in an exponent, is not an integer, or is not exact since its
+/// magnitude is at least 2^53.
+pub fn with_modulus(expression: &Expression, modulus: u64) -> Result<Expression> {
+    let recurse = |expression: &Expression| with_modulus(expression, modulus);
+    Ok(match expression {
+        Expression::Num(num) => {
+            check_integer_literal(*num)?;
+            Expression::Call {
+                name: "mod".to_string(),
+                args: vec![expression.clone(), Expression::Num(modulus as f64)],
+            }
+        }
+        Expression::Variable(_) => expression.clone(),
+        Expression::BinOp {
+            op: BinOp::Power,
+            lhs,
+            rhs,
+        } => {
+            check_integers(rhs)?;
+            Expression::BinOp {
+                op: BinOp::Power,
+                lhs: Box::new(recurse(lhs)?),
+                rhs: rhs.clone(),
+            }
+        }
+        Expression::BinOp { op, lhs, rhs } => Expression::BinOp {
+            op: *op,
+            lhs: Box::new(recurse(lhs)?),
+            rhs: Box::new(recurse(rhs)?),
+        },
+        Expression::UnOp { op, expr } => Expression::UnOp {
+            op: *op,
+            expr: Box::new(recurse(expr)?),
+        },
+        // Calls of `mod` already give their residues.
+        Expression::Call { name, .. } if name == "mod" => expression.clone(),
+        Expression::Call { name, args } => Expression::Call {
+            name: name.clone(),
+            args: args.iter().map(recurse).collect::<Result<_>>()?,
+        },
+        Expression::List(elements) => {
+            Expression::List(elements.iter().map(recurse).collect::<Result<_>>()?)
+        }
+        Expression::Lambda { params, body } => Expression::Lambda {
+            params: params.clone(),
+            body: Box::new(recurse(body)?),
+        },
+    })
+}
@@ -1,11 +1,14 @@
 //! Parser.
 
 use self::inner::*;
+use super::limits::Limits;
 use super::syntax::*;
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use lazy_static::lazy_static;
 use pest::{
+    error::LineColLocation,
     iterators::{Pair, Pairs},
-    prec_climber::{Assoc, Operator, PrecClimber},
+    pratt_parser::{Assoc, Op, PrattParser},
     Parser,
 };
 
@@ -23,64 +26,188 @@ mod inner {
 ///
 /// ## Operator Associativty
 ///
-/// For associativity of each operator, please follow [here](https://docs.rs/pest/latest/pest/prec_climber/struct.PrecClimber.html#examples).
+/// For associativity of each operator, please follow [here](https://docs.rs/pest/latest/pest/pratt_parser/struct.PrattParser.html#examples).
 ///
 /// e.g. `1+2+3` should be parsed into `(1+2)+3`, not `1+(2+3)` because the associativity of plus("add" in our hw) operator is `Left`.
+///
+/// ## Operator Precedence
+///
+/// From the lowest to the highest: `+` and `-`; `*` and `/`; prefix `-`; `+/-`; `^`; postfix `!` and `%`.
+/// e.g. `-2^2` is parsed into `-(2^2)`, and `2^3!` into `2^(3!)`.
 pub fn parse_command(line: &str) -> Result<Command> {
-    let pairs = SyntaxParser::parse(Rule::command, line)?;
-    match pairs.peek().unwrap().as_rule() {
-        Rule::expr => Ok(Command {
-            variable: None,
-            expression: turn_pairs_into_expressions(pairs.peek().unwrap().into_inner()),
-        }),
-        _ => Ok(Command {
-            variable: Some(pairs.peek().unwrap().as_str().to_string()),
-            expression: turn_pairs_into_expressions(pairs.clone().nth(1).unwrap().into_inner()),
-        }),
-    }
+    parse_command_with_limits(line, &Limits::default())
 }
 
-fn turn_pairs_into_expressions(line: Pairs<'_, Rule>) -> Expression {
-    let climber = PrecClimber::new(vec![
-        Operator::new(Rule::add, Assoc::Left) | Operator::new(Rule::subtract, Assoc::Left),
-        Operator::new(Rule::multiply, Assoc::Left) | Operator::new(Rule::divide, Assoc::Left),
-        Operator::new(Rule::power, Assoc::Right),
-    ]);
-    let infix = |lhs: Expression, op: Pair<'_, Rule>, rhs: Expression| match op.as_rule() {
-        Rule::add => Expression::BinOp {
-            op: BinOp::Add,
-            lhs: Box::new(lhs),
-            rhs: Box::new(rhs),
-        },
-        Rule::subtract => Expression::BinOp {
-            op: BinOp::Subtract,
-            lhs: Box::new(lhs),
-            rhs: Box::new(rhs),
-        },
-        Rule::multiply => Expression::BinOp {
-            op: BinOp::Multiply,
-            lhs: Box::new(lhs),
-            rhs: Box::new(rhs),
-        },
-        Rule::divide => Expression::BinOp {
-            op: BinOp::Divide,
-            lhs: Box::new(lhs),
-            rhs: Box::new(rhs),
+/// Parses command, rejecting it if it exceeds the given limits.
+pub fn parse_command_with_limits(line: &str, limits: &Limits) -> Result<Command> {
+    limits.check_source(line)?;
+    let mut pairs = SyntaxParser::parse(Rule::command, line)?;
+    let first = next_pair(&mut pairs, "command")?;
+    let command = match first.as_rule() {
+        Rule::expr => Command {
+            variable: None,
+            expression: turn_pairs_into_expressions(first.into_inner())?,
         },
-        Rule::power => Expression::BinOp {
-            op: BinOp::Power,
-            lhs: Box::new(lhs),
-            rhs: Box::new(rhs),
+        Rule::var => Command {
+            variable: Some(first.as_str().to_string()),
+            expression: turn_pairs_into_expressions(
+                next_pair(&mut pairs, "assignment")?.into_inner(),
+            )?,
         },
-        _ => unreachable!(),
+        rule => bail!("unexpected {:?} in command", rule),
     };
+    limits.check_expression(&command.expression)?;
+    Ok(command)
+}
+
+/// Returns the column (0-based, in characters) where parsing failed, if `error` is a syntax error
+/// returned by [`parse_command`].
+pub fn error_column(error: &anyhow::Error) -> Option<usize> {
+    let error = error.downcast_ref::<pest::error::Error<Rule>>()?;
+    match error.line_col {
+        LineColLocation::Pos((_, col)) | LineColLocation::Span((_, col), _) => Some(col - 1),
+    }
+}
+
+lazy_static! {
+    /// Operator table, from the lowest precedence to the highest.
+    static ref PRATT_PARSER: PrattParser<Rule> = PrattParser::new()
+        .op(Op::infix(Rule::add, Assoc::Left) | Op::infix(Rule::subtract, Assoc::Left))
+        .op(Op::infix(Rule::multiply, Assoc::Left) | Op::infix(Rule::divide, Assoc::Left))
+        .op(Op::prefix(Rule::negate))
+        .op(Op::infix(Rule::plus_minus, Assoc::Left))
+        .op(Op::infix(Rule::power, Assoc::Right))
+        .op(Op::postfix(Rule::factorial) | Op::postfix(Rule::percent));
+}
 
+/// Returns the next pair of the `what` being parsed.
+fn next_pair<'i>(pairs: &mut Pairs<'i, Rule>, what: &str) -> Result<Pair<'i, Rule>> {
+    pairs
+        .next()
+        .ok_or_else(|| anyhow!("unexpected end of {}", what))
+}
+
+fn turn_pairs_into_expressions(line: Pairs<'_, Rule>) -> Result<Expression> {
     let primary = |pair: Pair<'_, Rule>| match pair.as_rule() {
-        Rule::num => Expression::Num(pair.as_str().parse::<f64>().unwrap()),
-        Rule::var => Expression::Variable(pair.as_str().to_string()),
-        Rule::expr => turn_pairs_into_expressions(line.peek().unwrap().into_inner()),
-        _ => unreachable!(),
+        Rule::num => Ok(Expression::Num(pair.as_str().parse::<f64>()?)),
+        Rule::var => Ok(Expression::Variable(pair.as_str().to_string())),
+        // `255u8` is the cast `u8(255)`.
+        Rule::suffixed => {
+            let mut inner = pair.into_inner();
+            let num = next_pair(&mut inner, "literal")?.as_str().parse::<f64>()?;
+            Ok(Expression::Call {
+                name: next_pair(&mut inner, "literal")?.as_str().to_string(),
+                args: vec![Expression::Num(num)],
+            })
+        }
+        // `2026-10-18` is `date(2026, 10, 18)`, and `2026-10-18T09:30` is
+        // `datetime(2026, 10, 18, 9, 30, 0)`.
+        Rule::date => {
+            let mut args = pair
+                .as_str()
+                .split(|c: char| !c.is_ascii_digit())
+                .map(|field| Ok(Expression::Num(field.parse::<f64>()?)))
+                .collect::<Result<Vec<_>>>()?;
+            let name = if args.len() == 3 {
+                "date"
+            } else {
+                args.resize(6, Expression::Num(0.0));
+                "datetime"
+            };
+            Ok(Expression::Call {
+                name: name.to_string(),
+                args,
+            })
+        }
+        // `3 weeks` is `weeks(3)`.
+        Rule::duration => {
+            let mut inner = pair.into_inner();
+            let num = next_pair(&mut inner, "duration")?.as_str().parse::<f64>()?;
+            let unit = next_pair(&mut inner, "duration")?.as_str();
+            let name = if unit.ends_with('s') {
+                unit.to_string()
+            } else {
+                format!("{}s", unit)
+            };
+            Ok(Expression::Call {
+                name,
+                args: vec![Expression::Num(num)],
+            })
+        }
+        Rule::call => {
+            let mut inner = pair.into_inner();
+            let name = next_pair(&mut inner, "call")?.as_str().to_string();
+            let args = inner
+                .map(|arg| turn_pairs_into_expressions(arg.into_inner()))
+                .collect::<Result<_>>()?;
+            Ok(Expression::Call { name, args })
+        }
+        Rule::list => Ok(Expression::List(
+            pair.into_inner()
+                .map(|element| turn_pairs_into_expressions(element.into_inner()))
+                .collect::<Result<_>>()?,
+        )),
+        Rule::lambda => {
+            let mut inner = pair.into_inner();
+            let params = next_pair(&mut inner, "lambda")?
+                .into_inner()
+                .map(|param| param.as_str().to_string())
+                .collect();
+            let body = turn_pairs_into_expressions(next_pair(&mut inner, "lambda")?.into_inner())?;
+            Ok(Expression::Lambda {
+                params,
+                body: Box::new(body),
+            })
+        }
+        // A parenthesized expression is parsed on its own, at any depth.
+        Rule::expr => turn_pairs_into_expressions(pair.into_inner()),
+        rule => bail!("unexpected {:?} in expression", rule),
+    };
+
+    let prefix = |op: Pair<'_, Rule>, expr: Result<Expression>| {
+        let op = match op.as_rule() {
+            Rule::negate => UnOp::Negate,
+            rule => bail!("unexpected prefix operator {:?}", rule),
+        };
+        Ok(Expression::UnOp {
+            op,
+            expr: Box::new(expr?),
+        })
+    };
+
+    let postfix = |expr: Result<Expression>, op: Pair<'_, Rule>| {
+        let op = match op.as_rule() {
+            Rule::factorial => UnOp::Factorial,
+            Rule::percent => UnOp::Percent,
+            rule => bail!("unexpected postfix operator {:?}", rule),
+        };
+        Ok(Expression::UnOp {
+            op,
+            expr: Box::new(expr?),
+        })
+    };
+
+    let infix = |lhs: Result<Expression>, op: Pair<'_, Rule>, rhs: Result<Expression>| {
+        let op = match op.as_rule() {
+            Rule::add => BinOp::Add,
+            Rule::subtract => BinOp::Subtract,
+            Rule::multiply => BinOp::Multiply,
+            Rule::divide => BinOp::Divide,
+            Rule::power => BinOp::Power,
+            Rule::plus_minus => BinOp::PlusMinus,
+            rule => bail!("unexpected infix operator {:?}", rule),
+        };
+        Ok(Expression::BinOp {
+            op,
+            lhs: Box::new(lhs?),
+            rhs: Box::new(rhs?),
+        })
     };
 
-    climber.climb(line.clone(), primary, infix)
+    PRATT_PARSER
+        .map_primary(primary)
+        .map_prefix(prefix)
+        .map_postfix(postfix)
+        .map_infix(infix)
+        .parse(line)
 }
@@ -0,0 +1,358 @@
+//! Plotting expressions in one variable.
+//!
+//! A [`Plot`] samples the expression with [`Context::calc_expression`], and is rendered as text
+//! for the terminal (braille or block characters) or as SVG. Samples where the expression fails
+//! (e.g. `1 / 0`) or is not finite are left out, and neighboring samples are not connected across
+//! a discontinuity, so that the poles of `1 / x` are not drawn as vertical lines.
+
+use std::fmt::Write;
+
+use anyhow::*;
+
+use super::context::{Context, Value};
+use super::statistics::percentile;
+use super::syntax::Expression;
+
+/// Number of samples of a plot.
+const SAMPLES: usize = 512;
+
+/// Default width of a text plot, in characters.
+pub const DEFAULT_WIDTH: usize = 64;
+
+/// Default height of a text plot, in characters.
+pub const DEFAULT_HEIGHT: usize = 16;
+
+/// Characters a text plot is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Braille patterns, with 2 x 4 dots per character.
+    Braille,
+    /// Half blocks, with 1 x 2 dots per character.
+    Block,
+}
+
+impl Style {
+    /// Returns the number of dots per character, horizontally and vertically.
+    fn dots(self) -> (usize, usize) {
+        match self {
+            Style::Braille => (2, 4),
+            Style::Block => (1, 2),
+        }
+    }
+
+    /// Returns the character showing the dots of a cell, indexed by `[column][row]`.
+    fn cell(self, dots: &[[bool; 4]; 2]) -> char {
+        match self {
+            Style::Braille => {
+                // Bits of the dots in a braille pattern, indexed by `[column][row]`.
+                const BITS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+                let mut code = 0x2800;
+                for (column, bits) in BITS.iter().enumerate() {
+                    for (row, bit) in bits.iter().enumerate() {
+                        if dots[column][row] {
+                            code |= bit;
+                        }
+                    }
+                }
+                char::from_u32(code).unwrap_or(' ')
+            }
+            Style::Block => match (dots[0][0], dots[0][1]) {
+                (false, false) => ' ',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (true, true) => '█',
+            },
+        }
+    }
+}
+
+/// Sampled graph of an expression in one variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    /// Samples `(x, y)`, where `y` is `None` if the expression is undefined at `x`.
+    samples: Vec<(f64, Option<f64>)>,
+    /// Whether each sample is connected to the next one.
+    connected: Vec<bool>,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+}
+
+/// Evaluates the expression at `x`, returning `None` if it fails or is not a finite number.
+fn eval<V: Value>(
+    context: &mut Context<V>,
+    expression: &Expression,
+    variable: &str,
+    x: f64,
+) -> Option<f64> {
+    let _ = context.set_var(variable, V::from_num(x));
+    context
+        .calc_expression(expression)
+        .ok()
+        .and_then(|value| value.as_f64())
+        .filter(|y| y.is_finite())
+}
+
+impl Plot {
+    /// Samples the expression in `variable` from `a` to `b`, evaluating it in the context.
+    ///
+    /// The vertical range covers the samples, except that outliers (e.g. near poles) are cut off.
+    pub fn new<V: Value>(
+        context: &Context<V>,
+        expression: &Expression,
+        variable: &str,
+        a: f64,
+        b: f64,
+    ) -> Result<Self> {
+        if !(a.is_finite() && b.is_finite() && a < b) {
+            bail!("cannot plot from {} to {}", a, b);
+        }
+        let mut context = context.clone();
+        let x = |i: usize| a + (b - a) * i as f64 / (SAMPLES - 1) as f64;
+        let samples = (0..SAMPLES)
+            .map(|i| (x(i), eval(&mut context, expression, variable, x(i))))
+            .collect::<Vec<_>>();
+
+        let mut ys = samples.iter().filter_map(|(_, y)| *y).collect::<Vec<_>>();
+        if ys.is_empty() {
+            bail!("{} is undefined from {} to {}", expression, a, b);
+        }
+        ys.sort_by(f64::total_cmp);
+        let (min, max) = (ys[0], ys[ys.len() - 1]);
+        let (low, high) = match (percentile(&ys, 2_f64), percentile(&ys, 98_f64)) {
+            // Cuts off the outliers if they stretch the range much further than the bulk.
+            (Some(low), Some(high)) if max - min > 4_f64 * (high - low) => (low, high),
+            _ => (min, max),
+        };
+        let margin = if high > low {
+            (high - low) * 0.05
+        } else {
+            1_f64.max(high.abs() * 0.05)
+        };
+        let y_range = (low - margin, high + margin);
+
+        // Neighboring samples are disconnected if they jump across the whole plot, and the
+        // midpoint does not lie in between as it would for a steep but continuous function.
+        let span = y_range.1 - y_range.0;
+        let connected = samples
+            .windows(2)
+            .map(|pair| match (pair[0], pair[1]) {
+                ((x0, Some(y0)), (x1, Some(y1))) => {
+                    if (y1 - y0).abs() <= span {
+                        return true;
+                    }
+                    eval(&mut context, expression, variable, (x0 + x1) / 2_f64)
+                        .map_or(false, |mid| y0.min(y1) <= mid && mid <= y0.max(y1))
+                }
+                _ => false,
+            })
+            .collect();
+
+        Ok(Self {
+            samples,
+            connected,
+            x_range: (a, b),
+            y_range,
+        })
+    }
+
+    /// Returns the horizontal range.
+    pub fn x_range(&self) -> (f64, f64) {
+        self.x_range
+    }
+
+    /// Returns the vertical range.
+    pub fn y_range(&self) -> (f64, f64) {
+        self.y_range
+    }
+
+    /// Returns the sampled points, leaving out those where the expression is undefined.
+    pub fn points(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.samples.iter().filter_map(|(x, y)| y.map(|y| (*x, y)))
+    }
+
+    /// Returns the connected runs of samples, each as a list of points.
+    fn runs(&self) -> Vec<Vec<(f64, f64)>> {
+        let mut runs: Vec<Vec<(f64, f64)>> = Vec::new();
+        for (i, (x, y)) in self.samples.iter().enumerate() {
+            let y = match y {
+                Some(y) => *y,
+                None => continue,
+            };
+            match runs.last_mut() {
+                Some(run) if i > 0 && self.connected[i - 1] => run.push((*x, y)),
+                _ => runs.push(vec![(*x, y)]),
+            }
+        }
+        runs
+    }
+
+    /// Renders the plot as text of `width` by `height` characters, plus the axes' labels. Both
+    /// are at least 1.
+    pub fn render(&self, style: Style, width: usize, height: usize) -> String {
+        let (width, height) = (width.max(1), height.max(1));
+        let (dots_x, dots_y) = style.dots();
+        let (columns, rows) = (width * dots_x, height * dots_y);
+        let mut canvas = vec![vec![false; rows]; columns];
+        let (x0, x1) = self.x_range;
+        let (y0, y1) = self.y_range;
+        // Dot coordinates, with the row growing downwards. Rows out of the canvas are kept, so
+        // that lines leaving the plot are clipped rather than bent.
+        let column = |x: f64| (((x - x0) / (x1 - x0)) * (columns - 1) as f64).round() as isize;
+        let row = |y: f64| (((y1 - y) / (y1 - y0)) * (rows - 1) as f64).round() as isize;
+
+        let mut plot = |column: isize, row: isize| {
+            if (0..columns as isize).contains(&column) && (0..rows as isize).contains(&row) {
+                canvas[column as usize][row as usize] = true;
+            }
+        };
+        if y0 <= 0_f64 && 0_f64 <= y1 {
+            for column in 0..columns {
+                plot(column as isize, row(0_f64));
+            }
+        }
+        if x0 <= 0_f64 && 0_f64 <= x1 {
+            for row in 0..rows {
+                plot(column(0_f64), row as isize);
+            }
+        }
+        for run in self.runs() {
+            let mut points = run
+                .iter()
+                .map(|(x, y)| (column(*x), row(*y).clamp(-1, rows as isize)));
+            let mut previous = match points.next() {
+                Some(point) => point,
+                None => continue,
+            };
+            plot(previous.0, previous.1);
+            for point in points {
+                // Fills the rows between consecutive points, half at each column.
+                let (low, high) = (previous.1.min(point.1), previous.1.max(point.1));
+                let middle = (previous.1 + point.1) / 2;
+                for row in low..=high {
+                    let at_previous = (row <= middle) == (previous.1 <= point.1);
+                    plot(if at_previous { previous.0 } else { point.0 }, row);
+                }
+                previous = point;
+            }
+        }
+
+        let labels = [label(y1), label(y0)];
+        let margin = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut out = String::new();
+        for line in 0..height {
+            let label = match line {
+                0 => labels[0].as_str(),
+                _ if line == height - 1 => labels[1].as_str(),
+                _ => "",
+            };
+            let tick = if label.is_empty() { '│' } else { '┤' };
+            let _ = write!(out, "{:>margin$} {}", label, tick, margin = margin);
+            for cell in 0..width {
+                let mut dots = [[false; 4]; 2];
+                for (dx, column) in dots.iter_mut().enumerate().take(dots_x) {
+                    for (dy, dot) in column.iter_mut().enumerate().take(dots_y) {
+                        *dot = canvas[cell * dots_x + dx][line * dots_y + dy];
+                    }
+                }
+                out.push(style.cell(&dots));
+            }
+            out.push('\n');
+        }
+        let _ = writeln!(
+            out,
+            "{:>margin$} └{}",
+            "",
+            "─".repeat(width),
+            margin = margin
+        );
+        let (left, right) = (label(x0), label(x1));
+        let gap = (width + 1).saturating_sub(left.chars().count() + right.chars().count());
+        let _ = writeln!(
+            out,
+            "{:>margin$}  {}{}{}",
+            "",
+            left,
+            " ".repeat(gap),
+            right,
+            margin = margin
+        );
+        out
+    }
+
+    /// Renders the plot as a standalone SVG image of `width` by `height` pixels.
+    pub fn svg(&self, width: f64, height: f64) -> String {
+        let (x0, x1) = self.x_range;
+        let (y0, y1) = self.y_range;
+        let x = |value: f64| (value - x0) / (x1 - x0) * width;
+        let y = |value: f64| (y1 - value) / (y1 - y0) * height;
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+            w = width,
+            h = height
+        );
+        let _ = writeln!(
+            out,
+            "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>",
+            width, height
+        );
+        if y0 <= 0_f64 && 0_f64 <= y1 {
+            let _ = writeln!(
+                out,
+                "<line x1=\"0\" y1=\"{y:.2}\" x2=\"{w}\" y2=\"{y:.2}\" stroke=\"gray\"/>",
+                y = y(0_f64),
+                w = width
+            );
+        }
+        if x0 <= 0_f64 && 0_f64 <= x1 {
+            let _ = writeln!(
+                out,
+                "<line x1=\"{x:.2}\" y1=\"0\" x2=\"{x:.2}\" y2=\"{h}\" stroke=\"gray\"/>",
+                x = x(0_f64),
+                h = height
+            );
+        }
+        // The path is clipped to the image, so that segments leaving the plot are cut off.
+        let _ = write!(
+            out,
+            "<path fill=\"none\" stroke=\"steelblue\" stroke-width=\"1.5\" d=\""
+        );
+        for run in self.runs() {
+            for (i, (px, py)) in run.iter().enumerate() {
+                let command = if i == 0 { 'M' } else { 'L' };
+                let _ = write!(out, "{}{:.2},{:.2} ", command, x(*px), y(*py));
+            }
+        }
+        let _ = writeln!(out, "\"/>");
+        for (text, anchor, tx, ty) in [
+            (label(y1), "start", 4_f64, 14_f64),
+            (label(y0), "start", 4_f64, height - 4_f64),
+            (label(x0), "start", 4_f64, height - 20_f64),
+            (label(x1), "end", width - 4_f64, height - 20_f64),
+        ] {
+            let _ = writeln!(
+                out,
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"{}\" font-size=\"12\" font-family=\"monospace\">{}</text>",
+                tx, ty, anchor, text
+            );
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+}
+
+/// Formats the axis label with at most two decimals.
+fn label(value: f64) -> String {
+    let label = format!("{:.2}", value);
+    let label = label.trim_end_matches('0').trim_end_matches('.');
+    match label {
+        "-0" => "0".to_string(),
+        _ => label.to_string(),
+    }
+}
@@ -0,0 +1,191 @@
+//! Conversion between expressions and polynomials.
+//!
+//! An expression in at most one variable that only adds, subtracts, and multiplies, divides by
+//! constants, and raises to natural number powers is a polynomial. Expanding it into a
+//! [`Polynomial`] combines like terms, so that e.g. `(x + 1) ^ 2 - x ^ 2` becomes `2 * x + 1` and
+//! is evaluated without cancellation errors.
+
+use std::convert::TryFrom;
+
+use anyhow::*;
+
+use super::context::{Context, Value};
+use super::syntax::{BinOp, Expression, UnOp};
+use crate::assignments::assignment06::{Field, Polynomial, Ring, Semiring};
+
+/// Variable of polynomials converted to expressions by `From`.
+const DEFAULT_VARIABLE: &str = "x";
+
+/// Maximum degree of a power in an expanded polynomial. Multiplying polynomials takes time
+/// quadratic in their degrees.
+const MAX_DEGREE: f64 = 4096_f64;
+
+/// Returns the polynomial's value if it is a constant.
+fn constant(polynomial: &Polynomial<f64>) -> Option<f64> {
+    let mut value = 0_f64;
+    for (degree, coefficient) in polynomial.terms() {
+        if degree != 0 {
+            return None;
+        }
+        value = *coefficient;
+    }
+    Some(value)
+}
+
+/// Expands `expression` into a polynomial in `variable`, which is set by the first variable found.
+fn expand_with<'e>(
+    expression: &'e Expression,
+    variable: &mut Option<&'e str>,
+) -> Result<Polynomial<f64>> {
+    match expression {
+        Expression::Num(num) => Ok(Polynomial::from(*num)),
+        Expression::Variable(var) => match variable {
+            Some(variable) if variable != var => {
+                bail!("polynomial in both `{}` and `{}`", variable, var)
+            }
+            _ => {
+                *variable = Some(var);
+                Ok(Polynomial::x())
+            }
+        },
+        Expression::BinOp { op, lhs, rhs } => {
+            let percent = rhs.is_percent_of(*op);
+            let lhs = expand_with(lhs, variable)?;
+            let mut rhs = expand_with(rhs, variable)?;
+            if percent {
+                rhs = lhs.mul(&rhs);
+            }
+            match op {
+                BinOp::Add => Ok(lhs.add(&rhs)),
+                BinOp::Subtract => Ok(lhs.sub(&rhs)),
+                BinOp::Multiply => Ok(lhs.mul(&rhs)),
+                BinOp::Divide => match constant(&rhs).map(|divisor| divisor.inv()) {
+                    Some(Some(inverse)) => Ok(lhs.mul(&Polynomial::from(inverse))),
+                    Some(None) => bail!("cannot divide 0"),
+                    None => bail!("division by a non-constant polynomial"),
+                },
+                BinOp::Power => match constant(&rhs) {
+                    Some(exponent) if exponent >= 0_f64 && exponent.fract() == 0_f64 => {
+                        let degree = lhs.degree().unwrap_or(0);
+                        if degree as f64 * exponent > MAX_DEGREE {
+                            bail!("polynomial of degree above {}", MAX_DEGREE);
+                        }
+                        // Exponentiation by squaring.
+                        let mut exponent = exponent as u64;
+                        let (mut base, mut result) = (lhs, Polynomial::one());
+                        while exponent > 0 {
+                            if exponent % 2 == 1 {
+                                result = result.mul(&base);
+                            }
+                            base = base.mul(&base);
+                            exponent /= 2;
+                        }
+                        Ok(result)
+                    }
+                    _ => bail!("power with a non-natural exponent"),
+                },
+                BinOp::PlusMinus => bail!("polynomial with uncertain coefficients"),
+            }
+        }
+        Expression::UnOp { op, expr } => {
+            let operand = expand_with(expr, variable)?;
+            match op {
+                UnOp::Negate => Ok(operand.neg()),
+                UnOp::Percent => Ok(operand.mul(&Polynomial::from(0.01))),
+                UnOp::Factorial => match constant(&operand) {
+                    Some(operand) => Ok(Polynomial::from(f64::calc_unop(*op, &operand)?)),
+                    None => bail!("factorial of a non-constant polynomial"),
+                },
+            }
+        }
+        Expression::Call { name, .. } => bail!("function call `{}` in a polynomial", name),
+        Expression::List(_) => bail!("list in a polynomial"),
+        Expression::Lambda { .. } => bail!("lambda in a polynomial"),
+    }
+}
+
+/// Expands an expression in at most one variable into a polynomial in that variable.
+///
+/// Fails if the expression has more than one variable, divides by a non-constant, raises to a
+/// non-natural power, or calls a function.
+impl TryFrom<&Expression> for Polynomial<f64> {
+    type Error = Error;
+
+    fn try_from(expression: &Expression) -> Result<Self> {
+        expand_with(expression, &mut None)
+    }
+}
+
+/// Renders the polynomial as an expression in `x`.
+impl From<&Polynomial<f64>> for Expression {
+    fn from(polynomial: &Polynomial<f64>) -> Self {
+        to_expression(polynomial, DEFAULT_VARIABLE)
+    }
+}
+
+/// Renders the polynomial as an expression in `variable`, from the highest degree term to the
+/// lowest.
+pub fn to_expression(polynomial: &Polynomial<f64>, variable: &str) -> Expression {
+    let mut result: Option<Expression> = None;
+    for (degree, coefficient) in polynomial.terms().rev() {
+        let power = match degree {
+            0 => None,
+            1 => Some(Expression::Variable(variable.to_string())),
+            _ => Some(Expression::BinOp {
+                op: BinOp::Power,
+                lhs: Box::new(Expression::Variable(variable.to_string())),
+                rhs: Box::new(Expression::Num(degree as f64)),
+            }),
+        };
+        // Subtracts the term instead of adding a negative one, unless it leads the polynomial.
+        let (op, magnitude) = match result {
+            Some(_) if *coefficient < 0_f64 => (BinOp::Subtract, -coefficient),
+            _ => (BinOp::Add, *coefficient),
+        };
+        let term = match power {
+            None => Expression::Num(magnitude),
+            Some(power) if magnitude == 1_f64 => power,
+            Some(power) if magnitude == -1_f64 => Expression::UnOp {
+                op: UnOp::Negate,
+                expr: Box::new(power),
+            },
+            Some(power) => Expression::BinOp {
+                op: BinOp::Multiply,
+                lhs: Box::new(Expression::Num(magnitude)),
+                rhs: Box::new(power),
+            },
+        };
+        result = Some(match result {
+            None => term,
+            Some(lhs) => Expression::BinOp {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(term),
+            },
+        });
+    }
+    result.unwrap_or(Expression::Num(0_f64))
+}
+
+/// Expands an expression in at most one variable, returning the variable (if any) and the
+/// polynomial in it.
+pub fn expand(expression: &Expression) -> Result<(Option<&str>, Polynomial<f64>)> {
+    let mut variable = None;
+    let polynomial = expand_with(expression, &mut variable)?;
+    Ok((variable, polynomial))
+}
+
+/// Expands an expression as [`expand`] does, returning the expansion and its exact value if the
+/// polynomial's variable is bound in the context.
+pub fn expand_in<V: Value>(
+    expression: &Expression,
+    context: &Context<V>,
+) -> Result<(Expression, Option<f64>)> {
+    let (variable, polynomial) = expand(expression)?;
+    let value = match variable {
+        None => Some(0_f64),
+        Some(variable) => context.get_var(variable).and_then(|value| value.as_f64()),
+    };
+    let expansion = to_expression(&polynomial, variable.unwrap_or(DEFAULT_VARIABLE));
+    Ok((expansion, value.map(|value| polynomial.eval(value))))
+}
@@ -0,0 +1,268 @@
+//! Rendering expressions as LaTeX and MathML.
+
+use super::syntax::{BinOp, Expression, UnOp};
+
+/// Functions with their own LaTeX command.
+const LATEX_FUNCTIONS: [&str; 8] = ["sin", "cos", "tan", "exp", "ln", "log", "min", "max"];
+
+/// Returns the precedence of the typeset expression. Fractions are set apart by the fraction bar,
+/// so they only need parentheses as the base of a power or the operand of a postfix operator.
+fn precedence(expression: &Expression) -> u8 {
+    match expression {
+        Expression::BinOp {
+            op: BinOp::Divide, ..
+        } => BinOp::Power.precedence(),
+        _ => expression.precedence(),
+    }
+}
+
+/// Returns the minimum precedence of `op`'s operands that may go without parentheses. Operands
+/// that are typeset apart (fractions and exponents) are `None`.
+fn operand_precedences(op: BinOp) -> (Option<u8>, Option<u8>) {
+    let precedence = op.precedence();
+    match op {
+        BinOp::Divide => (None, None),
+        BinOp::Power => (Some(precedence + 1), None),
+        _ => (Some(precedence), Some(precedence + 1)),
+    }
+}
+
+/// Returns whether the operand needs parentheses.
+fn needs_parens(operand: &Expression, min: Option<u8>) -> bool {
+    min.map_or(false, |min| precedence(operand) < min)
+}
+
+/// Renders the expression as LaTeX math.
+///
+/// # Example
+///
+/// `(x + 1) ^ 2 / sqrt(2)` is rendered as `\frac{\left(x + 1\right)^{2}}{\sqrt{2}}`.
+pub fn latex(expression: &Expression) -> String {
+    let mut out = String::new();
+    write_latex(expression, &mut out);
+    out
+}
+
+fn write_latex_operand(expression: &Expression, min: Option<u8>, out: &mut String) {
+    if needs_parens(expression, min) {
+        out.push_str("\\left(");
+        write_latex(expression, out);
+        out.push_str("\\right)");
+    } else {
+        write_latex(expression, out);
+    }
+}
+
+fn write_latex(expression: &Expression, out: &mut String) {
+    match expression {
+        Expression::Num(num) => out.push_str(&num.to_string()),
+        Expression::Variable(var) => out.push_str(&latex_identifier(var)),
+        Expression::BinOp { op, lhs, rhs } => {
+            let (lhs_min, rhs_min) = operand_precedences(*op);
+            match op {
+                BinOp::Divide => {
+                    out.push_str("\\frac{");
+                    write_latex(lhs, out);
+                    out.push_str("}{");
+                    write_latex(rhs, out);
+                    out.push('}');
+                }
+                BinOp::Power => {
+                    write_latex_operand(lhs, lhs_min, out);
+                    out.push_str("^{");
+                    write_latex(rhs, out);
+                    out.push('}');
+                }
+                _ => {
+                    let symbol = match op {
+                        BinOp::Multiply => "\\cdot",
+                        BinOp::PlusMinus => "\\pm",
+                        _ => op.symbol(),
+                    };
+                    write_latex_operand(lhs, lhs_min, out);
+                    out.push(' ');
+                    out.push_str(symbol);
+                    out.push(' ');
+                    write_latex_operand(rhs, rhs_min, out);
+                }
+            }
+        }
+        Expression::UnOp { op, expr } => {
+            let min = Some(op.precedence());
+            match op {
+                UnOp::Negate => {
+                    out.push('-');
+                    write_latex_operand(expr, min, out);
+                }
+                UnOp::Factorial => {
+                    write_latex_operand(expr, min, out);
+                    out.push('!');
+                }
+                UnOp::Percent => {
+                    write_latex_operand(expr, min, out);
+                    out.push_str("\\%");
+                }
+            }
+        }
+        Expression::Call { name, args } => {
+            if name == "sqrt" && args.len() == 1 {
+                out.push_str("\\sqrt{");
+                write_latex(&args[0], out);
+                out.push('}');
+                return;
+            }
+            if LATEX_FUNCTIONS.contains(&name.as_str()) {
+                out.push('\\');
+                out.push_str(name);
+            } else {
+                out.push_str("\\operatorname{");
+                out.push_str(&name.replace('_', "\\_"));
+                out.push('}');
+            }
+            out.push_str("\\left(");
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_latex(arg, out);
+            }
+            out.push_str("\\right)");
+        }
+        Expression::List(elements) => {
+            out.push_str("\\left[");
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_latex(element, out);
+            }
+            out.push_str("\\right]");
+        }
+        Expression::Lambda { params, body } => {
+            let params = params
+                .iter()
+                .map(|param| latex_identifier(param))
+                .collect::<Vec<_>>();
+            match params.as_slice() {
+                [param] => out.push_str(param),
+                _ => {
+                    out.push_str("\\left(");
+                    out.push_str(&params.join(", "));
+                    out.push_str("\\right)");
+                }
+            }
+            out.push_str(" \\mapsto ");
+            write_latex(body, out);
+        }
+    }
+}
+
+/// Renders the variable name as a LaTeX identifier. Multi-letter names are set in italics as a
+/// whole so that they are not read as products.
+pub fn latex_identifier(name: &str) -> String {
+    let escaped = name.replace('$', "\\$").replace('_', "\\_");
+    if name.chars().count() > 1 && !name.starts_with('$') {
+        format!("\\mathit{{{}}}", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// Renders the expression as a presentation MathML `<math>` element.
+///
+/// # Example
+///
+/// `1 / x` is rendered as `<math><mfrac><mn>1</mn><mi>x</mi></mfrac></math>`.
+pub fn mathml(expression: &Expression) -> String {
+    format!("<math>{}</math>", mathml_row(expression))
+}
+
+/// Renders the expression as a single presentation MathML element, to be put in a `<math>`
+/// element.
+pub fn mathml_row(expression: &Expression) -> String {
+    match expression {
+        Expression::Num(num) if num.is_sign_negative() => {
+            format!("<mrow><mo>-</mo><mn>{}</mn></mrow>", -num)
+        }
+        Expression::Num(num) => format!("<mn>{}</mn>", num),
+        Expression::Variable(var) => format!("<mi>{}</mi>", var),
+        Expression::BinOp { op, lhs, rhs } => {
+            let (lhs_min, rhs_min) = operand_precedences(*op);
+            let lhs = mathml_operand(lhs, lhs_min);
+            let rhs = mathml_operand(rhs, rhs_min);
+            match op {
+                BinOp::Divide => format!("<mfrac>{}{}</mfrac>", lhs, rhs),
+                BinOp::Power => format!("<msup>{}{}</msup>", lhs, rhs),
+                _ => {
+                    let symbol = match op {
+                        BinOp::Multiply => "&#x22C5;",
+                        BinOp::PlusMinus => "&#xB1;",
+                        _ => op.symbol(),
+                    };
+                    format!("<mrow>{}<mo>{}</mo>{}</mrow>", lhs, symbol, rhs)
+                }
+            }
+        }
+        Expression::UnOp { op, expr } => {
+            let operand = mathml_operand(expr, Some(op.precedence()));
+            if op.is_prefix() {
+                format!("<mrow><mo>{}</mo>{}</mrow>", op.symbol(), operand)
+            } else {
+                format!("<mrow>{}<mo>{}</mo></mrow>", operand, op.symbol())
+            }
+        }
+        Expression::Call { name, args } => {
+            if name == "sqrt" && args.len() == 1 {
+                return format!("<msqrt>{}</msqrt>", mathml_row(&args[0]));
+            }
+            let args = args
+                .iter()
+                .map(mathml_row)
+                .collect::<Vec<_>>()
+                .join("<mo>,</mo>");
+            // U+2061 is the invisible "function application" operator.
+            format!(
+                "<mrow><mi>{}</mi><mo>&#x2061;</mo><mrow><mo>(</mo>{}<mo>)</mo></mrow></mrow>",
+                name, args
+            )
+        }
+        Expression::List(elements) => {
+            let elements = elements
+                .iter()
+                .map(mathml_row)
+                .collect::<Vec<_>>()
+                .join("<mo>,</mo>");
+            format!("<mrow><mo>[</mo>{}<mo>]</mo></mrow>", elements)
+        }
+        Expression::Lambda { params, body } => {
+            let params = params
+                .iter()
+                .map(|param| format!("<mi>{}</mi>", param))
+                .collect::<Vec<_>>();
+            let params = match params.as_slice() {
+                [param] => param.clone(),
+                _ => format!(
+                    "<mrow><mo>(</mo>{}<mo>)</mo></mrow>",
+                    params.join("<mo>,</mo>")
+                ),
+            };
+            // U+21A6 is the "maps to" arrow.
+            format!(
+                "<mrow>{}<mo>&#x21A6;</mo>{}</mrow>",
+                params,
+                mathml_row(body)
+            )
+        }
+    }
+}
+
+fn mathml_operand(expression: &Expression, min: Option<u8>) -> String {
+    if needs_parens(expression, min) {
+        format!(
+            "<mrow><mo>(</mo>{}<mo>)</mo></mrow>",
+            mathml_row(expression)
+        )
+    } else {
+        mathml_row(expression)
+    }
+}
@@ -0,0 +1,185 @@
+//! Lines of a calculator session, shared by `calc` and the language server.
+//!
+//! A line is blank, a session command starting with `:`, or a command. Session commands are:
+//!
+//! - `:undo` and `:redo`, which undo and redo the latest assignment;
+//! - `:history <var>`, which shows every value of the variable and the command that produced it;
+//!   and
+//! - `:mod <n>` and `:mod off`, which switch the kind of values and are left to the caller.
+//!
+//! Two functions are handled here rather than by the context, since they do not compute a value:
+//! `plot(expr, x, a, b)` draws a whole line, and `expand(expr)` expands a polynomial. An `expand`
+//! inside an expression is replaced by the expansion, or by its exact value if the polynomial's
+//! variable is bound.
+
+use anyhow::*;
+
+use super::context::{Context, Value};
+use super::parser;
+use super::plot::Plot;
+use super::polynomial;
+use super::syntax::{Command, Expression};
+
+/// What running a line did.
+#[derive(Debug)]
+pub enum Outcome<V> {
+    /// Nothing, for a blank line.
+    Blank,
+    /// Ran `:undo`, `:redo`, or `:history`, producing the lines to show.
+    Report(Vec<String>),
+    /// Asked to switch the kind of values with `:mod`, whose argument is given.
+    Mode(String),
+    /// Sampled `plot(expr, x, a, b)`.
+    Plot(Plot),
+    /// Expanded `expand(expr)` whose variable is not bound.
+    Expansion(Expression),
+    /// Calculated the command as entered, assigning the value to the variable.
+    Calculated {
+        /// The command as entered.
+        command: Command,
+        /// The assigned variable, e.g. `$0` for a command without one.
+        variable: String,
+        /// The calculated value.
+        value: V,
+    },
+}
+
+/// Runs the line. `prepare` rewrites the command's expression before it is calculated.
+pub fn run_line<V: Value>(
+    context: &mut Context<V>,
+    line: &str,
+    prepare: impl Fn(&Expression, &Context<V>) -> Result<Expression>,
+) -> Result<Outcome<V>> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(Outcome::Blank);
+    }
+    if let Some(command) = line.strip_prefix(':') {
+        return run_session_command(context, command);
+    }
+
+    let command = parser::parse_command(line)?;
+    if let (None, Expression::Call { name, args }) = (&command.variable, &command.expression) {
+        if name == "plot" {
+            return plot(context, args).map(Outcome::Plot);
+        }
+        if name == "expand" && args.len() == 1 {
+            let (expansion, value) = polynomial::expand_in(&args[0], context)?;
+            if value.is_none() {
+                return Ok(Outcome::Expansion(expansion));
+            }
+        }
+    }
+    let expression = prepare(&expand_calls(&command.expression, context)?, context)?;
+    let (variable, value) = context.calc_command_with_source(
+        &Command {
+            variable: command.variable.clone(),
+            expression,
+        },
+        line,
+    )?;
+    Ok(Outcome::Calculated {
+        command,
+        variable,
+        value,
+    })
+}
+
+/// Runs the session command (the line without the leading `:`).
+fn run_session_command<V: Value>(context: &mut Context<V>, command: &str) -> Result<Outcome<V>> {
+    let mut words = command.split_whitespace();
+    let report = match (words.next(), words.next(), words.next()) {
+        (Some("undo"), None, _) => vec![match context.undo() {
+            Some(assignment) => match assignment.previous() {
+                Some(previous) => format!("{} = {}", assignment.variable(), previous),
+                None => format!("{} is unset", assignment.variable()),
+            },
+            None => "nothing to undo".to_string(),
+        }],
+        (Some("redo"), None, _) => vec![match context.redo() {
+            Some(assignment) => format!("{} = {}", assignment.variable(), assignment.value()),
+            None => "nothing to redo".to_string(),
+        }],
+        (Some("history"), Some(variable), None) => context
+            .history(variable)
+            .map(|assignment| format!("{} => {}", assignment.source(), assignment.value()))
+            .collect(),
+        (Some("mod"), Some(argument), None) => return Ok(Outcome::Mode(argument.to_string())),
+        _ => bail!("unknown session command `:{}`", command),
+    };
+    Ok(Outcome::Report(report))
+}
+
+/// Samples `plot(expr, x, a, b)`, i.e., `expr` in `x` from `a` to `b`.
+fn plot<V: Value>(context: &Context<V>, args: &[Expression]) -> Result<Plot> {
+    if args.len() != 4 {
+        bail!("plot takes 4 arguments, but {} were given", args.len());
+    }
+    let variable = match &args[1] {
+        Expression::Variable(variable) => variable,
+        arg => bail!("cannot plot in `{}`, which is not a variable", arg),
+    };
+    let bound = |arg: &Expression| match context.calc_expression(arg)?.as_f64() {
+        Some(bound) => Ok(bound),
+        None => bail!("plot bound `{}` is not a number", arg),
+    };
+    let expression = expand_calls_in(&args[0], context, true)?;
+    Plot::new(
+        context,
+        &expression,
+        variable,
+        bound(&args[2])?,
+        bound(&args[3])?,
+    )
+}
+
+/// Replaces the calls of `expand` by the expansions, or by their values if the polynomials'
+/// variables are bound.
+///
+/// Fails on a call of `plot`, which draws a whole line and has no value.
+fn expand_calls<V: Value>(expression: &Expression, context: &Context<V>) -> Result<Expression> {
+    expand_calls_in(expression, context, false)
+}
+
+/// Replaces the calls of `expand` as [`expand_calls`] does. In the body of a function, i.e., a
+/// lambda or a plotted expression, whose parameters may shadow the variables of the context, the
+/// expansions are kept.
+fn expand_calls_in<V: Value>(
+    expression: &Expression,
+    context: &Context<V>,
+    in_lambda: bool,
+) -> Result<Expression> {
+    let recurse = |expression: &Expression| expand_calls_in(expression, context, in_lambda);
+    Ok(match expression {
+        Expression::Num(_) | Expression::Variable(_) => expression.clone(),
+        Expression::BinOp { op, lhs, rhs } => Expression::BinOp {
+            op: *op,
+            lhs: Box::new(recurse(lhs)?),
+            rhs: Box::new(recurse(rhs)?),
+        },
+        Expression::UnOp { op, expr } => Expression::UnOp {
+            op: *op,
+            expr: Box::new(recurse(expr)?),
+        },
+        Expression::Call { name, args } if name == "expand" && args.len() == 1 => {
+            match polynomial::expand_in(&recurse(&args[0])?, context)? {
+                (_, Some(value)) if !in_lambda => Expression::Num(value),
+                (expansion, _) => expansion,
+            }
+        }
+        Expression::Call { name, .. } if name == "plot" => {
+            bail!("`plot` draws a whole line and cannot be part of an expression")
+        }
+        Expression::Call { name, args } => Expression::Call {
+            name: name.clone(),
+            args: args.iter().map(recurse).collect::<Result<_>>()?,
+        },
+        Expression::List(elements) => {
+            Expression::List(elements.iter().map(recurse).collect::<Result<_>>()?)
+        }
+        Expression::Lambda { params, body } => Expression::Lambda {
+            params: params.clone(),
+            body: Box::new(expand_calls_in(body, context, true)?),
+        },
+    })
+}
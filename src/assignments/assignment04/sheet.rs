@@ -0,0 +1,474 @@
+//! Spreadsheets.
+//!
+//! A [`Sheet`] is a grid of cells read from CSV. A cell holds a number, text, or a formula such as
+//! `=A1 + B2 * 2` or `=sum(A1:A10)`, whose cell references are variables of the calculator and
+//! whose ranges are lists of cells by rows. [`Sheet::evaluate`] calculates every formula once,
+//! after the cells it refers to, and reports circular references.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::result::Result::Ok;
+
+use anyhow::*;
+
+use super::context::{Context, Dynamic};
+use super::parser;
+use super::syntax::Expression;
+
+/// Maximum number of cells of a range.
+const MAX_RANGE_CELLS: usize = 100_000;
+
+/// Position of a cell, e.g. `B3` for column 1 and row 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CellRef {
+    /// Row, from 0.
+    pub row: usize,
+    /// Column, from 0.
+    pub column: usize,
+}
+
+impl CellRef {
+    /// Parses a reference of uppercase column letters followed by a row number from 1, e.g. `AA10`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let digits = name.find(|c: char| c.is_ascii_digit())?;
+        let (letters, number) = name.split_at(digits);
+        if letters.is_empty()
+            || !letters.chars().all(|c| c.is_ascii_uppercase())
+            || !number.chars().all(|c| c.is_ascii_digit())
+            || number.starts_with('0')
+        {
+            return None;
+        }
+        let column = letters.chars().try_fold(0_usize, |column, c| {
+            column
+                .checked_mul(26)?
+                .checked_add(c as usize - 'A' as usize + 1)
+        })?;
+        Some(Self {
+            row: number.parse::<usize>().ok()? - 1,
+            column: column - 1,
+        })
+    }
+}
+
+/// Prints the reference, e.g. `B3`.
+impl fmt::Display for CellRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut letters = Vec::new();
+        let mut column = self.column + 1;
+        while column > 0 {
+            letters.push((b'A' + ((column - 1) % 26) as u8) as char);
+            column = (column - 1) / 26;
+        }
+        let letters = letters.into_iter().rev().collect::<String>();
+        write!(f, "{}{}", letters, self.row + 1)
+    }
+}
+
+/// Content of a cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    /// Empty cell, which formulas read as `0`.
+    Empty,
+    /// Number.
+    Number(f64),
+    /// Text, which formulas cannot read.
+    Text(String),
+    /// Formula, without the leading `=`.
+    Formula(String),
+}
+
+impl Cell {
+    /// Reads the cell's content.
+    pub fn parse(content: &str) -> Self {
+        let trimmed = content.trim();
+        if trimmed.is_empty() {
+            return Cell::Empty;
+        }
+        if let Some(formula) = trimmed.strip_prefix('=') {
+            return Cell::Formula(formula.to_string());
+        }
+        match trimmed.parse::<f64>() {
+            Ok(number) => Cell::Number(number),
+            Err(_) => Cell::Text(content.to_string()),
+        }
+    }
+}
+
+/// Reason a cell has no value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CellError {
+    /// The cell is on a cycle of references, from and back to the first cell.
+    Cycle(Vec<CellRef>),
+    /// The formula refers to a text cell.
+    Text(CellRef),
+    /// The formula is invalid or fails.
+    Formula(String),
+}
+
+impl CellError {
+    /// Returns the code of the error written in place of the cell's value.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CellError::Cycle(_) => "#CYCLE!",
+            CellError::Text(_) => "#VALUE!",
+            CellError::Formula(_) => "#ERROR!",
+        }
+    }
+}
+
+impl fmt::Display for CellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellError::Cycle(cycle) => {
+                write!(f, "circular reference ")?;
+                for cell in cycle {
+                    write!(f, "{} -> ", cell)?;
+                }
+                write!(f, "{}", cycle[0])
+            }
+            CellError::Text(cell) => write!(f, "{} is text, not a number", cell),
+            CellError::Formula(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Value of an evaluated cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Evaluated {
+    /// Empty cell.
+    Empty,
+    /// Text.
+    Text(String),
+    /// Number, or value of a formula.
+    Value(Dynamic),
+    /// Formula without a value.
+    Error(CellError),
+}
+
+/// Prints the value, or the error's code.
+impl fmt::Display for Evaluated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Evaluated::Empty => Ok(()),
+            Evaluated::Text(text) => write!(f, "{}", text),
+            Evaluated::Value(value) => write!(f, "{}", value),
+            Evaluated::Error(error) => write!(f, "{}", error.code()),
+        }
+    }
+}
+
+/// Grid of cells.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sheet {
+    rows: Vec<Vec<Cell>>,
+}
+
+/// Evaluation state of a formula cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Its references are being evaluated.
+    Visiting,
+    /// Evaluated.
+    Done,
+}
+
+impl Sheet {
+    /// Reads the sheet from CSV.
+    pub fn from_csv(csv: &str) -> Result<Self> {
+        Ok(Self {
+            rows: parse_csv(csv)?
+                .iter()
+                .map(|row| row.iter().map(|content| Cell::parse(content)).collect())
+                .collect(),
+        })
+    }
+
+    /// Returns the rows of cells.
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.rows
+    }
+
+    /// Returns the cell, which is empty outside the grid.
+    pub fn cell(&self, cell: CellRef) -> &Cell {
+        self.rows
+            .get(cell.row)
+            .and_then(|row| row.get(cell.column))
+            .unwrap_or(&Cell::Empty)
+    }
+
+    /// Evaluates the formulas in the context, whose functions and variables they can use, and
+    /// returns the values of the cells.
+    ///
+    /// Each formula is calculated once, after the cells it refers to. The cells on a cycle of
+    /// references fail with [`CellError::Cycle`], and formulas referring to a failed cell fail
+    /// with its error.
+    pub fn evaluate(&self, context: &Context<Dynamic>) -> Vec<Vec<Evaluated>> {
+        let mut context = context.clone();
+        let mut formulas = HashMap::new();
+        for (row, cells) in self.rows.iter().enumerate() {
+            for (column, cell) in cells.iter().enumerate() {
+                let name = CellRef { row, column };
+                match cell {
+                    Cell::Number(number) => {
+                        let _ = context.set_var(&name.to_string(), Dynamic::Num(*number));
+                    }
+                    Cell::Formula(formula) => {
+                        let formula = parse_formula(formula);
+                        let references = match &formula {
+                            Ok(expression) => references(expression),
+                            Err(_) => vec![],
+                        };
+                        let _ = formulas.insert(name, (formula, references));
+                    }
+                    Cell::Empty | Cell::Text(_) => {}
+                }
+            }
+        }
+        for (_, references) in formulas.values() {
+            for reference in references {
+                if *self.cell(*reference) == Cell::Empty {
+                    let _ = context.set_var(&reference.to_string(), Dynamic::Num(0_f64));
+                }
+            }
+        }
+
+        // Depth-first search, on an explicit stack so that long chains of references do not
+        // overflow the call stack.
+        let mut states = HashMap::new();
+        let mut results: HashMap<CellRef, Result<Dynamic, CellError>> = HashMap::new();
+        let mut starts = formulas.keys().copied().collect::<Vec<_>>();
+        starts.sort();
+        for start in starts {
+            if states.contains_key(&start) {
+                continue;
+            }
+            let _ = states.insert(start, State::Visiting);
+            let mut stack = vec![(start, 0)];
+            while let Some((cell, next)) = stack.last_mut() {
+                let (cell, references) = (*cell, &formulas[cell].1);
+                if let Some(reference) = references.get(*next) {
+                    *next += 1;
+                    if !formulas.contains_key(reference) {
+                        continue;
+                    }
+                    match states.get(reference) {
+                        None => {
+                            let _ = states.insert(*reference, State::Visiting);
+                            stack.push((*reference, 0));
+                        }
+                        Some(State::Visiting) => {
+                            let position = stack
+                                .iter()
+                                .position(|(cell, _)| cell == reference)
+                                .expect("visiting cells are on the stack");
+                            let cycle = stack[position..]
+                                .iter()
+                                .map(|(cell, _)| *cell)
+                                .collect::<Vec<_>>();
+                            for cell in &cycle {
+                                let _ = results.insert(*cell, Err(CellError::Cycle(cycle.clone())));
+                            }
+                        }
+                        Some(State::Done) => {}
+                    }
+                    continue;
+                }
+                let _ = stack.pop();
+                let _ = states.insert(cell, State::Done);
+                if results.contains_key(&cell) {
+                    continue;
+                }
+                let result = self.evaluate_formula(&formulas[&cell], &results, &context);
+                if let Ok(value) = &result {
+                    let _ = context.set_var(&cell.to_string(), value.clone());
+                }
+                let _ = results.insert(cell, result);
+            }
+        }
+
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(row, cells)| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .map(|(column, cell)| match cell {
+                        Cell::Empty => Evaluated::Empty,
+                        Cell::Number(number) => Evaluated::Value(Dynamic::Num(*number)),
+                        Cell::Text(text) => Evaluated::Text(text.clone()),
+                        Cell::Formula(_) => match &results[&CellRef { row, column }] {
+                            Ok(value) => Evaluated::Value(value.clone()),
+                            Err(error) => Evaluated::Error(error.clone()),
+                        },
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Evaluates the formula in the context, which holds the values of the cells it refers to.
+    fn evaluate_formula(
+        &self,
+        (formula, references): &(Result<Expression, CellError>, Vec<CellRef>),
+        results: &HashMap<CellRef, Result<Dynamic, CellError>>,
+        context: &Context<Dynamic>,
+    ) -> Result<Dynamic, CellError> {
+        let expression = formula.as_ref().map_err(Clone::clone)?;
+        for reference in references {
+            match self.cell(*reference) {
+                Cell::Empty | Cell::Number(_) => {}
+                Cell::Text(_) => return Err(CellError::Text(*reference)),
+                Cell::Formula(_) => {
+                    if let Err(error) = &results[reference] {
+                        return Err(error.clone());
+                    }
+                }
+            }
+        }
+        context
+            .calc_expression(expression)
+            .map_err(|error| CellError::Formula(error.to_string()))
+    }
+}
+
+/// Parses the formula, after replacing each range like `A1:B2` by the list of its cells by rows,
+/// e.g. `[A1, B1, A2, B2]`.
+fn parse_formula(formula: &str) -> Result<Expression, CellError> {
+    let parse = || {
+        let command = parser::parse_command(&expand_ranges(formula)?)?;
+        if let Some(variable) = command.variable {
+            bail!("formulas cannot assign {}", variable);
+        }
+        Ok(command.expression)
+    };
+    parse().map_err(|error: Error| CellError::Formula(error.to_string()))
+}
+
+/// Replaces each range by the list of its cells.
+fn expand_ranges(formula: &str) -> Result<String> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$';
+    let mut expanded = String::new();
+    let mut rest = formula;
+    while let Some(colon) = rest.find(':') {
+        let start = rest[..colon].rfind(|c| !is_word(c)).map_or(0, |i| i + 1);
+        let end = rest[colon + 1..]
+            .find(|c| !is_word(c))
+            .map_or(rest.len(), |i| colon + 1 + i);
+        let range = (
+            CellRef::from_name(&rest[start..colon]),
+            CellRef::from_name(&rest[colon + 1..end]),
+        );
+        let (first, last) = match range {
+            (Some(first), Some(last)) => (first, last),
+            // Not a range, e.g. a time of day.
+            _ => {
+                expanded.push_str(&rest[..=colon]);
+                rest = &rest[colon + 1..];
+                continue;
+            }
+        };
+        let (rows, columns) = (
+            first.row.min(last.row)..=first.row.max(last.row),
+            first.column.min(last.column)..=first.column.max(last.column),
+        );
+        if rows.clone().count().saturating_mul(columns.clone().count()) > MAX_RANGE_CELLS {
+            bail!(
+                "range {} has more than {} cells",
+                &rest[start..end],
+                MAX_RANGE_CELLS
+            );
+        }
+        expanded.push_str(&rest[..start]);
+        expanded.push('[');
+        for (i, row) in rows.enumerate() {
+            for (j, column) in columns.clone().enumerate() {
+                if i > 0 || j > 0 {
+                    expanded.push_str(", ");
+                }
+                expanded.push_str(&CellRef { row, column }.to_string());
+            }
+        }
+        expanded.push(']');
+        rest = &rest[end..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Returns the cells the expression refers to, in order and without repetitions.
+fn references(expression: &Expression) -> Vec<CellRef> {
+    fn collect(expression: &Expression, references: &mut Vec<CellRef>) {
+        if let Expression::Variable(variable) = expression {
+            if let Some(cell) = CellRef::from_name(variable) {
+                if !references.contains(&cell) {
+                    references.push(cell);
+                }
+            }
+        }
+        for child in expression.children() {
+            collect(child, references);
+        }
+    }
+    let mut references = Vec::new();
+    collect(expression, &mut references);
+    references
+}
+
+/// Parses CSV: fields are separated by commas, and quoted fields may contain commas, newlines, and
+/// quotes written twice.
+pub fn parse_csv(csv: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let (mut row, mut field) = (Vec::new(), String::new());
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        let _ = chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => bail!("unterminated quoted field in row {}", rows.len() + 1),
+                }
+            },
+            ',' => row.push(std::mem::take(&mut field)),
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Writes the rows as CSV, quoting the fields that need it.
+pub fn write_csv<T: fmt::Display>(rows: &[Vec<T>]) -> String {
+    let mut csv = String::new();
+    for row in rows {
+        for (i, field) in row.iter().enumerate() {
+            if i > 0 {
+                csv.push(',');
+            }
+            let field = field.to_string();
+            if field.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+                csv.push('"');
+                csv.push_str(&field.replace('"', "\"\""));
+                csv.push('"');
+            } else {
+                csv.push_str(&field);
+            }
+        }
+        csv.push('\n');
+    }
+    csv
+}
@@ -0,0 +1,125 @@
+//! Statistics over lists of numbers.
+//!
+//! [`register`] adds them to a calculator's function registry, so that e.g.
+//! `stddev([2, 4, 4, 4, 5, 5, 7, 9])` can be evaluated. The median and the mode are those of
+//! [`assignment03`](crate::assignments::assignment03), generalized to `f64`.
+
+use anyhow::*;
+
+use super::context::{Context, Dynamic};
+use crate::assignments::assignment03::{middle_by, mode_by};
+
+/// Returns the arithmetic mean, or `None` if the list is empty.
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Returns the median, i.e., the middle value when sorted, or the mean of the two middle values
+/// if there is an even number of values. Returns `None` if the list is empty.
+pub fn median(values: &[f64]) -> Option<f64> {
+    middle_by(values.to_vec(), f64::total_cmp).map(|(lower, upper)| (lower + upper) / 2_f64)
+}
+
+/// Returns the smallest of the most frequent values, or `None` if the list is empty.
+pub fn mode(values: &[f64]) -> Option<f64> {
+    mode_by(values.to_vec(), f64::total_cmp)
+}
+
+/// Returns the sample variance (dividing by `n - 1`), or `None` if there are fewer than two
+/// values.
+pub fn variance(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = mean(values)?;
+    let squares = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>();
+    Some(squares / (values.len() - 1) as f64)
+}
+
+/// Returns the sample standard deviation, or `None` if there are fewer than two values.
+pub fn stddev(values: &[f64]) -> Option<f64> {
+    variance(values).map(f64::sqrt)
+}
+
+/// Returns the `p`-th percentile (`0 <= p <= 100`), interpolating linearly between the closest
+/// ranks. Returns `None` if the list is empty or `p` is out of range.
+///
+/// The 0th, 50th, and 100th percentiles are the minimum, the median, and the maximum.
+pub fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    if values.is_empty() || !(0_f64..=100_f64).contains(&p) {
+        return None;
+    }
+    let mut values = values.to_vec();
+    values.sort_by(f64::total_cmp);
+    let rank = p / 100_f64 * (values.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(values[lower] + (rank - lower as f64) * (values[upper] - values[lower]))
+}
+
+/// Returns the minimum, or `None` if the list is empty.
+pub fn min(values: &[f64]) -> Option<f64> {
+    values.iter().copied().reduce(f64::min)
+}
+
+/// Returns the maximum, or `None` if the list is empty.
+pub fn max(values: &[f64]) -> Option<f64> {
+    values.iter().copied().reduce(f64::max)
+}
+
+/// Statistic of a list of numbers.
+type Statistic = fn(&[f64]) -> Option<f64>;
+
+/// Returns the numbers in the list.
+pub(crate) fn numbers(name: &str, value: &Dynamic) -> Result<Vec<f64>> {
+    let list = match value {
+        Dynamic::List(list) => list,
+        _ => bail!("{} expects a list, but got {}", name, value),
+    };
+    list.iter()
+        .map(|element| match element {
+            Dynamic::Num(num) => Ok(*num),
+            _ => bail!("{} expects numbers, but got {}", name, element),
+        })
+        .collect()
+}
+
+/// Registers `mean`, `median`, `mode`, `variance`, `stddev`, `min`, and `max` taking a list, and
+/// `percentile` taking a list and `p`.
+pub fn register(context: &mut Context<Dynamic>) -> &mut Context<Dynamic> {
+    let statistics: [(&str, Statistic); 7] = [
+        ("mean", mean),
+        ("median", median),
+        ("mode", mode),
+        ("variance", variance),
+        ("stddev", stddev),
+        ("min", min),
+        ("max", max),
+    ];
+    for (name, statistic) in statistics {
+        let _ = context.register_fn(name, 1, move |args| {
+            let values = numbers(name, &args[0])?;
+            match statistic(&values) {
+                Some(value) => Ok(Dynamic::Num(value)),
+                None => bail!("{} of {} values", name, values.len()),
+            }
+        });
+    }
+    context.register_fn("percentile", 2, |args| {
+        let values = numbers("percentile", &args[0])?;
+        let p = match args[1] {
+            Dynamic::Num(p) => p,
+            _ => bail!("percentile expects a number, but got {}", args[1]),
+        };
+        match percentile(&values, p) {
+            Some(value) => Ok(Dynamic::Num(value)),
+            None if values.is_empty() => bail!("percentile of an empty list"),
+            None => bail!("percentile {} is not between 0 and 100", p),
+        }
+    })
+}
@@ -1,5 +1,7 @@
 //! Syntax.
 
+use std::fmt;
+
 /// Command of the form "<expression>" or "<var> = <expression>".
 #[derive(Debug, Clone, PartialEq)]
 pub struct Command {
@@ -10,7 +12,7 @@ pub struct Command {
 }
 
 /// Binary operators.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BinOp {
     /// Add.
     Add,
@@ -22,6 +24,19 @@ pub enum BinOp {
     Divide,
     /// Power.
     Power,
+    /// Measurement with uncertainty (`value +/- sigma`).
+    PlusMinus,
+}
+
+/// Unary operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnOp {
+    /// Negate (prefix `-`).
+    Negate,
+    /// Factorial (postfix `!`).
+    Factorial,
+    /// Percent (postfix `%`).
+    Percent,
 }
 
 /// Expression.
@@ -40,4 +55,214 @@ pub enum Expression {
         /// Rhs.
         rhs: Box<Expression>,
     },
+    /// Unary operation.
+    UnOp {
+        /// Operator.
+        op: UnOp,
+        /// Operand.
+        expr: Box<Expression>,
+    },
+    /// Function call.
+    Call {
+        /// Function name.
+        name: String,
+        /// Arguments.
+        args: Vec<Expression>,
+    },
+    /// List (`[1, 2, 3]`).
+    List(Vec<Expression>),
+    /// Anonymous function (`x -> x ^ 2` or `(x, y) -> x * y`).
+    Lambda {
+        /// Parameters.
+        params: Vec<String>,
+        /// Body.
+        body: Box<Expression>,
+    },
+}
+
+impl Expression {
+    /// Returns the immediate subexpressions.
+    pub fn children(&self) -> Vec<&Expression> {
+        match self {
+            Expression::Num(_) | Expression::Variable(_) => vec![],
+            Expression::BinOp { lhs, rhs, .. } => vec![lhs, rhs],
+            Expression::UnOp { expr, .. } => vec![expr],
+            Expression::Call { args, .. } | Expression::List(args) => args.iter().collect(),
+            Expression::Lambda { body, .. } => vec![body],
+        }
+    }
+
+    /// Returns whether the expression is a percentage `b%` of the lhs of `op`, see
+    /// [`BinOp::takes_percent_of`].
+    pub fn is_percent_of(&self, op: BinOp) -> bool {
+        op.takes_percent_of()
+            && matches!(
+                self,
+                Expression::UnOp {
+                    op: UnOp::Percent,
+                    ..
+                }
+            )
+    }
+}
+
+/// Prints the command in the calculator's syntax.
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.variable {
+            Some(variable) => write!(f, "{} = {}", variable, self.expression),
+            None => write!(f, "{}", self.expression),
+        }
+    }
+}
+
+impl BinOp {
+    /// Returns the operator's symbol.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Subtract => "-",
+            BinOp::Multiply => "*",
+            BinOp::Divide => "/",
+            BinOp::Power => "^",
+            BinOp::PlusMinus => "+/-",
+        }
+    }
+
+    /// Returns the operator's precedence in the parser's operator table.
+    pub(crate) fn precedence(self) -> u8 {
+        match self {
+            BinOp::Add | BinOp::Subtract => 1,
+            BinOp::Multiply | BinOp::Divide => 2,
+            BinOp::PlusMinus => 4,
+            BinOp::Power => 5,
+        }
+    }
+
+    /// Returns whether the operator is right associative.
+    pub(crate) fn is_right_assoc(self) -> bool {
+        self == BinOp::Power
+    }
+
+    /// Returns whether a percentage rhs of the operator is one of the lhs. As on a calculator,
+    /// `200 + 10%` is `200 + 200 * 10%`, i.e., `220`.
+    pub fn takes_percent_of(self) -> bool {
+        matches!(self, BinOp::Add | BinOp::Subtract)
+    }
+}
+
+impl UnOp {
+    /// Returns the operator's symbol.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            UnOp::Negate => "-",
+            UnOp::Factorial => "!",
+            UnOp::Percent => "%",
+        }
+    }
+
+    /// Returns whether the operator is written before its operand.
+    pub fn is_prefix(self) -> bool {
+        self == UnOp::Negate
+    }
+
+    /// Returns the operator's precedence in the parser's operator table.
+    pub(crate) fn precedence(self) -> u8 {
+        if self.is_prefix() {
+            3
+        } else {
+            6
+        }
+    }
+}
+
+impl Expression {
+    /// Returns the precedence of the expression's outermost operator.
+    pub(crate) fn precedence(&self) -> u8 {
+        match self {
+            // A negative number reads like a negation.
+            Expression::Num(num) if num.is_sign_negative() => UnOp::Negate.precedence(),
+            Expression::Num(_)
+            | Expression::Variable(_)
+            | Expression::Call { .. }
+            | Expression::List(_) => 7,
+            Expression::BinOp { op, .. } => op.precedence(),
+            Expression::UnOp { op, .. } => op.precedence(),
+            // The body extends as far as possible.
+            Expression::Lambda { .. } => 0,
+        }
+    }
+
+    /// Writes the operand, parenthesized if its precedence is below `min`.
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, min: u8) -> fmt::Result {
+        if self.precedence() < min {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// Prints the expression in the calculator's syntax with as few parentheses as possible, so that
+/// parsing it back yields the same value.
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Num(num) => write!(f, "{}", num),
+            Expression::Variable(var) => write!(f, "{}", var),
+            Expression::BinOp { op, lhs, rhs } => {
+                let precedence = op.precedence();
+                let (lhs_min, rhs_min) = if op.is_right_assoc() {
+                    (precedence + 1, precedence)
+                } else {
+                    (precedence, precedence + 1)
+                };
+                lhs.fmt_operand(f, lhs_min)?;
+                write!(f, " {} ", op.symbol())?;
+                rhs.fmt_operand(f, rhs_min)
+            }
+            Expression::UnOp { op, expr } => {
+                if op.is_prefix() {
+                    write!(f, "{}", op.symbol())?;
+                    expr.fmt_operand(f, op.precedence())
+                } else {
+                    expr.fmt_operand(f, op.precedence())?;
+                    write!(f, "{}", op.symbol())
+                }
+            }
+            Expression::Call { name, args } => {
+                write!(f, "{}(", name)?;
+                fmt_elements(f, args)?;
+                write!(f, ")")
+            }
+            Expression::List(elements) => {
+                write!(f, "[")?;
+                fmt_elements(f, elements)?;
+                write!(f, "]")
+            }
+            Expression::Lambda { params, body } => {
+                fmt_params(f, params)?;
+                write!(f, " -> {}", body)
+            }
+        }
+    }
+}
+
+/// Writes the comma-separated arguments of a call or elements of a list.
+fn fmt_elements(f: &mut fmt::Formatter<'_>, elements: &[Expression]) -> fmt::Result {
+    for (i, element) in elements.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", element)?;
+    }
+    Ok(())
+}
+
+/// Writes the parameters of a lambda, parenthesized unless there is exactly one.
+pub(crate) fn fmt_params(f: &mut fmt::Formatter<'_>, params: &[String]) -> fmt::Result {
+    match params {
+        [param] => write!(f, "{}", param),
+        _ => write!(f, "({})", params.join(", ")),
+    }
 }
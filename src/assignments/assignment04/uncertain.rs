@@ -0,0 +1,156 @@
+//! Measurements with uncertainty.
+//!
+//! Uncertainties are propagated to first order (linear error propagation). Each `value +/- sigma`
+//! introduces an independent error source, and every value remembers how it depends on each
+//! source. Hence correlated terms cancel out: `x - x` has zero uncertainty while `x + x` has
+//! twice the uncertainty of `x`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::*;
+
+use super::context::Value;
+use super::syntax::{BinOp, UnOp};
+
+/// Identifier of the next fresh error source.
+static NEXT_SOURCE: AtomicUsize = AtomicUsize::new(0);
+
+/// Value with first-order uncertainty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Uncertain {
+    value: f64,
+    /// Contribution of each independent error source (keyed by its id) to the value, i.e.,
+    /// `(∂value/∂source) * sigma(source)`.
+    components: BTreeMap<usize, f64>,
+}
+
+impl Uncertain {
+    /// Creates an exact value.
+    pub fn exact(value: f64) -> Self {
+        Self {
+            value,
+            components: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a measurement `value ± sigma` with a fresh, independent error source.
+    pub fn new(value: f64, sigma: f64) -> Self {
+        let mut components = BTreeMap::new();
+        if sigma != 0_f64 {
+            let source = NEXT_SOURCE.fetch_add(1, Ordering::Relaxed);
+            let _unused = components.insert(source, sigma.abs());
+        }
+        Self { value, components }
+    }
+
+    /// Returns the nominal value.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns the standard uncertainty.
+    pub fn sigma(&self) -> f64 {
+        self.components.values().map(|c| c * c).sum::<f64>().sqrt()
+    }
+
+    /// Returns the value `value` whose sensitivity to `operand` is `doperand`.
+    fn propagate_unary(value: f64, operand: &Self, doperand: f64) -> Self {
+        let mut components = operand.components.clone();
+        for c in components.values_mut() {
+            *c *= doperand;
+        }
+        components.retain(|_, c| *c != 0_f64);
+        Self { value, components }
+    }
+
+    /// Returns the value `value` whose sensitivities to `lhs` and `rhs` are `dlhs` and `drhs`.
+    fn propagate(value: f64, lhs: &Self, dlhs: f64, rhs: &Self, drhs: f64) -> Self {
+        let mut components = BTreeMap::new();
+        for (source, c) in &lhs.components {
+            let _unused = components.insert(*source, dlhs * c);
+        }
+        for (source, c) in &rhs.components {
+            let entry = components.entry(*source).or_insert(0_f64);
+            *entry += drhs * c;
+        }
+        components.retain(|_, c| *c != 0_f64);
+        Self { value, components }
+    }
+}
+
+impl Value for Uncertain {
+    fn from_num(num: f64) -> Self {
+        Self::exact(num)
+    }
+
+    fn calc_unop(op: UnOp, operand: &Self) -> Result<Self> {
+        match op {
+            UnOp::Negate => Ok(Self::propagate_unary(-operand.value, operand, -1_f64)),
+            UnOp::Factorial => {
+                if !operand.components.is_empty() {
+                    bail!("factorial of an uncertain number");
+                }
+                Ok(Self::exact(f64::calc_unop(op, &operand.value)?))
+            }
+            UnOp::Percent => Ok(Self::propagate_unary(
+                operand.value / 100_f64,
+                operand,
+                0.01,
+            )),
+        }
+    }
+
+    fn calc_binop(op: BinOp, lhs: &Self, rhs: &Self) -> Result<Self> {
+        let (a, b) = (lhs.value, rhs.value);
+        match op {
+            BinOp::Add => Ok(Self::propagate(a + b, lhs, 1_f64, rhs, 1_f64)),
+            BinOp::Subtract => Ok(Self::propagate(a - b, lhs, 1_f64, rhs, -1_f64)),
+            BinOp::Multiply => Ok(Self::propagate(a * b, lhs, b, rhs, a)),
+            BinOp::Divide => {
+                if b == 0_f64 {
+                    bail!("cannot divide 0");
+                }
+                Ok(Self::propagate(a / b, lhs, 1_f64 / b, rhs, -a / (b * b)))
+            }
+            BinOp::Power => {
+                let value = a.powf(b);
+                // `d(a^b)/db = a^b * ln(a)` is only needed (and only defined for `a > 0`) if the
+                // exponent is uncertain.
+                let dexp = if rhs.components.is_empty() {
+                    0_f64
+                } else {
+                    value * a.ln()
+                };
+                Ok(Self::propagate(
+                    value,
+                    lhs,
+                    b * a.powf(b - 1_f64),
+                    rhs,
+                    dexp,
+                ))
+            }
+            BinOp::PlusMinus => {
+                if !rhs.components.is_empty() {
+                    bail!("uncertainty should be an exact number");
+                }
+                let mut result = Self::new(a, b);
+                for (source, c) in &lhs.components {
+                    let _unused = result.components.insert(*source, *c);
+                }
+                Ok(result)
+            }
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        Some(self.value)
+    }
+}
+
+impl fmt::Display for Uncertain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ± {}", self.value, self.sigma())
+    }
+}
@@ -312,4 +312,1404 @@ mod test {
             })
             .is_err());
     }
+
+    #[test]
+    fn test_uncertain() {
+        let mut ctx = context::Context::<uncertain::Uncertain>::default();
+        let mut calc = |line: &str| {
+            ctx.calc_command(&parser::parse_command(line).unwrap())
+                .unwrap()
+                .1
+        };
+
+        let x = calc("x = 9.81 +/- 0.02");
+        assert_eq!((x.value(), x.sigma()), (9.81, 0.02));
+
+        // Correlated terms cancel out.
+        let zero = calc("x - x");
+        assert_eq!((zero.value(), zero.sigma()), (0.0, 0.0));
+        let double = calc("x + x");
+        assert!((double.sigma() - 0.04).abs() < 1e-12);
+
+        // Independent terms add in quadrature.
+        let _ = calc("y = 3 ± 0.4");
+        let sum = calc("x - y");
+        assert!((sum.sigma() - (0.02_f64.powi(2) + 0.4_f64.powi(2)).sqrt()).abs() < 1e-12);
+
+        // Relative uncertainties add in quadrature for products.
+        let product = calc("2 * x * y");
+        let relative = ((0.02_f64 / 9.81).powi(2) + (0.4_f64 / 3.0).powi(2)).sqrt();
+        assert!((product.sigma() / product.value() - relative).abs() < 1e-12);
+
+        assert_eq!(format!("{}", calc("1 +/- 0.5")), "1 ± 0.5");
+        assert!(context::Context::new()
+            .calc_command(&parser::parse_command("1 +/- 0.5").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_embedding() {
+        let mut ctx = context::Context::new();
+        let _ = ctx
+            .set_var("rate", 0.5)
+            .set_var("base", 10.0)
+            .register_fn("max", 2, |args| Ok(args[0].max(args[1])))
+            .register_fn("checked_sqrt", 1, |args| {
+                if args[0] < 0.0 {
+                    anyhow::bail!("negative")
+                }
+                Ok(args[0].sqrt())
+            });
+
+        assert_eq!(
+            parser::parse_command("max(base * rate, 3)").unwrap(),
+            Command {
+                variable: None,
+                expression: Expression::Call {
+                    name: "max".into(),
+                    args: vec![
+                        Expression::BinOp {
+                            op: BinOp::Multiply,
+                            lhs: Expression::Variable("base".into()).into(),
+                            rhs: Expression::Variable("rate".into()).into(),
+                        },
+                        Expression::Num(3.0),
+                    ],
+                },
+            }
+        );
+
+        let mut calc = |line: &str| ctx.calc_command(&parser::parse_command(line)?);
+        assert_eq!(calc("max(base * rate, 3)").unwrap(), ("$0".into(), 5.0));
+        assert_eq!(
+            calc("y = checked_sqrt(max(rate, 16))").unwrap(),
+            ("y".into(), 4.0)
+        );
+        assert!(calc("checked_sqrt(0 - 1)").is_err());
+        assert!(calc("max(1)").is_err());
+        assert!(calc("min(1, 2)").is_err());
+
+        assert_eq!(ctx.get_var("y"), Some(&4.0));
+        assert_eq!(ctx.remove_var("rate"), Some(0.5));
+        assert_eq!(ctx.get_var("rate"), None);
+        let mut variables = ctx.variables().collect::<Vec<_>>();
+        variables.sort_by(|lhs, rhs| lhs.0.cmp(rhs.0));
+        assert_eq!(variables, vec![("$0", &5.0), ("base", &10.0), ("y", &4.0)]);
+        let mut functions = ctx.functions().collect::<Vec<_>>();
+        functions.sort_unstable();
+        assert_eq!(functions, vec![("checked_sqrt", 1), ("max", 2)]);
+    }
+
+    #[test]
+    fn test_limits() {
+        use std::time::Duration;
+
+        use crate::assignments::assignment04::limits::*;
+
+        let exceeded = |result: anyhow::Result<_>| {
+            *result
+                .map(|_: Command| ())
+                .unwrap_err()
+                .downcast_ref::<LimitExceeded>()
+                .unwrap()
+        };
+
+        // Deeply nested parentheses are rejected before parsing.
+        let nested = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert_eq!(
+            exceeded(parser::parse_command(&nested)),
+            LimitExceeded::Depth(256)
+        );
+
+        // So are other shapes that the parser recurses into or that build deep trees, closed or
+        // not, which used to overflow the stack.
+        for deep in [
+            format!("{}1{}", "[".repeat(5_000), "]".repeat(5_000)),
+            "[".repeat(5_000),
+            format!("{}1", "-".repeat(20_000)),
+            vec!["2"; 20_000].join("^"),
+            format!("{}1", "x -> ".repeat(20_000)),
+            format!("f({}1)", "y -> ".repeat(20_000)),
+            vec!["1"; 100_000].join("-"),
+            format!("1{}", "!".repeat(100_000)),
+            format!("{}1", "-(".repeat(10_000)),
+        ] {
+            assert_eq!(
+                exceeded(parser::parse_command(&deep)),
+                LimitExceeded::Depth(256)
+            );
+        }
+        assert_eq!(
+            exceeded(parser::parse_command(&"1".repeat(1 << 20))),
+            LimitExceeded::Length(256 * 1024)
+        );
+        // Long but shallow input is not, nor are the signs of exponents.
+        let list = format!("[{}]", vec!["-1e-5 + 2"; 10_000].join(", "));
+        assert!(parser::parse_command(&list).is_ok());
+        assert!(parser::parse_command(&vec!["1e-5"; 200].join(" + ")).is_ok());
+
+        let limits = Limits {
+            max_depth: 8,
+            max_nodes: 16,
+            ..Limits::default()
+        };
+        assert!(parser::parse_command_with_limits("((((1))))", &limits).is_ok());
+        let chain = vec!["1"; 9].join(" - ");
+        assert_eq!(
+            exceeded(parser::parse_command_with_limits(&chain, &limits)),
+            LimitExceeded::Depth(8)
+        );
+        let wide = "(1 + 1) * (1 + 1) * (1 + 1) * (1 + 1) * (1 + 1)";
+        assert_eq!(
+            exceeded(parser::parse_command_with_limits(wide, &limits)),
+            LimitExceeded::Nodes(16)
+        );
+
+        // The context enforces its limits on expressions from any source.
+        let mut ctx = context::Context::new();
+        let wide = parser::parse_command(wide).unwrap().expression;
+        assert_eq!(ctx.calc_expression(&wide).unwrap(), 32.0);
+        let _ = ctx.set_limits(limits);
+        assert_eq!(
+            *ctx.calc_expression(&wide)
+                .unwrap_err()
+                .downcast_ref::<LimitExceeded>()
+                .unwrap(),
+            LimitExceeded::Nodes(16)
+        );
+
+        let calc = |limits: Limits, line: &str| {
+            let mut ctx = context::Context::new();
+            let _ = ctx.set_limits(limits);
+            ctx.calc_command(&parser::parse_command(line).unwrap())
+                .unwrap_err()
+                .downcast_ref::<LimitExceeded>()
+                .copied()
+        };
+        let steps = Limits {
+            max_steps: 4,
+            ..Limits::default()
+        };
+        assert_eq!(calc(steps, "1 + 2 * 3"), Some(LimitExceeded::Steps(4)));
+        let bits = Limits {
+            max_integer_bits: Some(64),
+            ..Limits::default()
+        };
+        assert_eq!(calc(bits, "2 ^ 64"), Some(LimitExceeded::IntegerBits(64)));
+        assert_eq!(
+            calc(bits, "10 ^ 10 ^ 10"),
+            Some(LimitExceeded::IntegerBits(64))
+        );
+        assert_eq!(calc(bits, "2 ^ 63 / 0"), None);
+        let time = Limits {
+            max_time: Some(Duration::ZERO),
+            ..Limits::default()
+        };
+        assert_eq!(calc(time, "1"), Some(LimitExceeded::Time(Duration::ZERO)));
+    }
+
+    #[test]
+    fn test_unary_operators() {
+        let unop = |op, expr: Expression| Expression::UnOp {
+            op,
+            expr: expr.into(),
+        };
+
+        // "-2 ^ 2" is "-(2 ^ 2)", and "2 ^ 3!" is "2 ^ (3!)".
+        assert_eq!(
+            parser::parse_command("-2 ^ 3!").unwrap().expression,
+            unop(
+                UnOp::Negate,
+                Expression::BinOp {
+                    op: BinOp::Power,
+                    lhs: Expression::Num(2.0).into(),
+                    rhs: unop(UnOp::Factorial, Expression::Num(3.0)).into(),
+                }
+            )
+        );
+        assert_eq!(
+            parser::parse_command("1 - -x%").unwrap().expression,
+            Expression::BinOp {
+                op: BinOp::Subtract,
+                lhs: Expression::Num(1.0).into(),
+                rhs: unop(
+                    UnOp::Negate,
+                    unop(UnOp::Percent, Expression::Variable("x".into()))
+                )
+                .into(),
+            }
+        );
+        assert!(parser::parse_command("3 !! 2").is_err());
+
+        let mut ctx = context::Context::new();
+        let mut calc = |line: &str| ctx.calc_command(&parser::parse_command(line)?).map(|r| r.1);
+        assert_eq!(calc("-2 ^ 2").unwrap(), -4.0);
+        assert_eq!(calc("2 ^ -1").unwrap(), 0.5);
+        assert_eq!(calc("2 * (3 + 4)!").unwrap(), 10080.0);
+        assert_eq!(calc("3!!").unwrap(), 720.0);
+        assert_eq!(calc("50%").unwrap(), 0.5);
+        // Adding or subtracting a percentage takes it of the lhs.
+        assert_eq!(calc("200 + 10%").unwrap(), 220.0);
+        assert_eq!(calc("200 - 10% - 50%").unwrap(), 90.0);
+        assert_eq!(calc("200 * 10%").unwrap(), 20.0);
+        assert_eq!(calc("1 - -50%").unwrap(), 1.5);
+        assert!(calc("(0 - 1)!").is_err());
+        assert!(calc("2.5!").is_err());
+    }
+
+    #[test]
+    fn test_polynomial() {
+        use std::convert::TryFrom;
+
+        use crate::assignments::assignment06::Polynomial;
+
+        let expand = |line: &str| {
+            Polynomial::<f64>::try_from(&parser::parse_command(line).unwrap().expression)
+        };
+
+        let poly = expand("(x + 1) ^ 2 - x ^ 2 - 2 * x").unwrap();
+        assert_eq!(poly, Polynomial::from(1.0));
+        assert_eq!(poly.eval(1e8), 1.0);
+
+        let poly = expand("-(x - 2) * (x + 2) / 4 + x ^ 3 * 2").unwrap();
+        assert_eq!(
+            Expression::from(&poly).to_string(),
+            "2 * x ^ 3 - 0.25 * x ^ 2 + 1"
+        );
+        assert_eq!(poly.eval(2.0), 16.0);
+
+        let expression = parser::parse_command("(t - 1) ^ 3").unwrap().expression;
+        let (variable, poly) = polynomial::expand(&expression).unwrap();
+        assert_eq!(
+            polynomial::to_expression(&poly, variable.unwrap()).to_string(),
+            "t ^ 3 - 3 * t ^ 2 + 3 * t - 1"
+        );
+
+        assert!(expand("x * y").is_err());
+        assert!(expand("1 / x").is_err());
+        assert!(expand("x ^ 0.5").is_err());
+        assert!(expand("x ^ x").is_err());
+        assert!(expand("f(x)").is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        for line in [
+            "1 + 2 * 3",
+            "(1 + 2) * 3",
+            "1 - (2 - 3)",
+            "1 - 2 - 3",
+            "2 ^ 3 ^ 2",
+            "(2 ^ 3) ^ 2",
+            "-2 ^ 2",
+            "(-2) ^ 2",
+            "(x + 1)! * 5%",
+            "max(1, x +/- 0.5) / -y",
+        ] {
+            let expression = parser::parse_command(line).unwrap().expression;
+            assert_eq!(expression.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let render = |line: &str| {
+            let expression = parser::parse_command(line).unwrap().expression;
+            (render::latex(&expression), render::mathml(&expression))
+        };
+
+        assert_eq!(
+            render("(x + 1) ^ 2 / sqrt(2)"),
+            (
+                r"\frac{\left(x + 1\right)^{2}}{\sqrt{2}}".into(),
+                "<math><mfrac><msup><mrow><mo>(</mo><mrow><mi>x</mi><mo>+</mo><mn>1</mn></mrow>\
+                 <mo>)</mo></mrow><mn>2</mn></msup><msqrt><mn>2</mn></msqrt></mfrac></math>"
+                    .into()
+            )
+        );
+        assert_eq!(
+            render("(1 / x) ^ (2 ^ n)"),
+            (
+                r"\left(\frac{1}{x}\right)^{2^{n}}".into(),
+                "<math><msup><mrow><mo>(</mo><mfrac><mn>1</mn><mi>x</mi></mfrac><mo>)</mo></mrow>\
+                 <msup><mn>2</mn><mi>n</mi></msup></msup></math>"
+                    .into()
+            )
+        );
+        assert_eq!(
+            render("a - (b - c) * -d"),
+            (
+                r"a - \left(b - c\right) \cdot -d".into(),
+                "<math><mrow><mi>a</mi><mo>-</mo><mrow><mrow><mo>(</mo><mrow><mi>b</mi><mo>-</mo>\
+                 <mi>c</mi></mrow><mo>)</mo></mrow><mo>&#x22C5;</mo><mrow><mo>-</mo><mi>d</mi></mrow>\
+                 </mrow></mrow></math>"
+                    .into()
+            )
+        );
+        assert_eq!(
+            render("(-2) ^ 2 + (x / 2)! * 5%").0,
+            r"\left(-2\right)^{2} + \left(\frac{x}{2}\right)! \cdot 5\%"
+        );
+        assert_eq!(
+            render("$0 * rate_2 +/- max(1, ln(x))").0,
+            r"\$0 \cdot \mathit{rate\_2} \pm \max\left(1, \ln\left(x\right)\right)"
+        );
+        assert_eq!(
+            render("checked_sqrt(1 / 2 / 3)").0,
+            r"\operatorname{checked\_sqrt}\left(\frac{\frac{1}{2}}{3}\right)"
+        );
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut ctx = context::Context::new();
+        for line in ["x = 1", "x = x + 1", "2 * x", "x = x * 10"] {
+            let _ = ctx
+                .calc_command(&parser::parse_command(line).unwrap())
+                .unwrap();
+        }
+
+        let history = ctx
+            .history("x")
+            .map(|assignment| (assignment.source().to_string(), *assignment.value()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            history,
+            vec![
+                ("x = 1".into(), 1.0),
+                ("x = x + 1".into(), 2.0),
+                ("x = x * 10".into(), 20.0)
+            ]
+        );
+
+        // Undoing restores the previous value and the anonymous variable counter.
+        assert_eq!(ctx.undo().unwrap().previous(), Some(&2.0));
+        assert_eq!(ctx.get_var("x"), Some(&2.0));
+        assert_eq!(ctx.undo().unwrap().variable(), "$0");
+        assert_eq!(ctx.get_var("$0"), None);
+        assert_eq!(ctx.current_counter(), 0);
+        assert_eq!(ctx.redo().unwrap().value(), &4.0);
+        assert_eq!(ctx.current_counter(), 1);
+        assert_eq!(ctx.history("x").count(), 2);
+
+        // A new assignment discards the undone ones.
+        let _ = ctx
+            .calc_command(&parser::parse_command("x = 0").unwrap())
+            .unwrap();
+        assert!(ctx.redo().is_none());
+
+        // The journal is bounded.
+        let _ = ctx.set_history_limit(2);
+        assert!(ctx.undo().is_some());
+        assert!(ctx.undo().is_some());
+        assert!(ctx.undo().is_none());
+        assert_eq!(ctx.get_var("x"), Some(&2.0));
+    }
+
+    #[test]
+    fn test_lsp() {
+        use json::Json;
+
+        fn frame(messages: &[&str]) -> Vec<u8> {
+            messages
+                .iter()
+                .map(|message| format!("Content-Length: {}\r\n\r\n{}", message.len(), message))
+                .collect::<String>()
+                .into_bytes()
+        }
+
+        fn unframe(output: &[u8]) -> Vec<Json> {
+            let output = std::str::from_utf8(output).unwrap();
+            output
+                .split("Content-Length: ")
+                .skip(1)
+                .map(|message| Json::parse(message.split_once("\r\n\r\n").unwrap().1).unwrap())
+                .collect()
+        }
+
+        let open = r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///a.calc","languageId":"calc","version":1,"text":"x = 2\ny = x * 3\n1 / 0\nz = (1 +\nx = y - 1\nx + w\n"}}}"#;
+        let hover_operator = r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/hover","params":{"textDocument":{"uri":"file:///a.calc"},"position":{"line":1,"character":6}}}"#;
+        let hover_x = r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/hover","params":{"textDocument":{"uri":"file:///a.calc"},"position":{"line":1,"character":4}}}"#;
+        let definition = r#"{"jsonrpc":"2.0","id":3,"method":"textDocument/definition","params":{"textDocument":{"uri":"file:///a.calc"},"position":{"line":5,"character":0}}}"#;
+        let completion = r#"{"jsonrpc":"2.0","id":4,"method":"textDocument/completion","params":{"textDocument":{"uri":"file:///a.calc"},"position":{"line":3,"character":0}}}"#;
+        let unknown = r#"{"jsonrpc":"2.0","id":5,"method":"workspace/symbol","params":{}}"#;
+        let exit = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+        let input = frame(&[
+            open,
+            hover_operator,
+            hover_x,
+            definition,
+            completion,
+            unknown,
+            exit,
+        ]);
+
+        let mut context = context::Context::<context::Dynamic>::default();
+        let _ = context.register_fn("sqrt", 1, |args| match args[0] {
+            context::Dynamic::Num(num) => Ok(num.sqrt().into()),
+            _ => anyhow::bail!("not a number"),
+        });
+        let mut output = Vec::new();
+        lsp::Server::new(context)
+            .serve(input.as_slice(), &mut output)
+            .unwrap();
+        let messages = unframe(&output);
+        assert_eq!(messages.len(), 6);
+
+        // Diagnostics of the evaluation error, the syntax error, and the unbound variable.
+        let diagnostics = messages[0]
+            .pointer(&["params", "diagnostics"])
+            .and_then(Json::as_array)
+            .unwrap()
+            .iter()
+            .map(|diagnostic| {
+                let position = |key| {
+                    let position = diagnostic.pointer(&["range", key]).unwrap();
+                    (
+                        position.get("line").and_then(Json::as_usize).unwrap(),
+                        position.get("character").and_then(Json::as_usize).unwrap(),
+                    )
+                };
+                (position("start"), position("end"))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec![((2, 0), (2, 5)), ((3, 8), (3, 8)), ((5, 0), (5, 5))]
+        );
+
+        let hover = |message: &Json| {
+            message
+                .pointer(&["result", "contents", "value"])
+                .and_then(Json::as_str)
+                .map(str::to_string)
+        };
+        assert_eq!(hover(&messages[1]), None);
+        assert_eq!(hover(&messages[2]), Some("x = 2".into()));
+
+        // `x` on the last line was last assigned on line 4.
+        let range = messages[3].pointer(&["result", "range", "start"]).unwrap();
+        assert_eq!(range.get("line").and_then(Json::as_usize), Some(4));
+        assert_eq!(range.get("character").and_then(Json::as_usize), Some(0));
+
+        let labels = messages[4]
+            .get("result")
+            .and_then(Json::as_array)
+            .unwrap()
+            .iter()
+            .map(|item| item.get("label").and_then(Json::as_str).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["sqrt", "x", "y"]);
+
+        assert!(messages[5].pointer(&["error", "code"]).is_some());
+    }
+
+    #[test]
+    fn test_lambda() {
+        use context::Dynamic;
+
+        let command = parser::parse_command("f = (x, y) -> x * y + 1").unwrap();
+        assert_eq!(
+            command.expression,
+            Expression::Lambda {
+                params: vec!["x".into(), "y".into()],
+                body: Expression::BinOp {
+                    op: BinOp::Add,
+                    lhs: Expression::BinOp {
+                        op: BinOp::Multiply,
+                        lhs: Expression::Variable("x".into()).into(),
+                        rhs: Expression::Variable("y".into()).into(),
+                    }
+                    .into(),
+                    rhs: Expression::Num(1.0).into(),
+                }
+                .into(),
+            }
+        );
+        assert_eq!(command.to_string(), "f = (x, y) -> x * y + 1");
+        assert_eq!(
+            parser::parse_command("map(x -> x ^ 2, l)")
+                .unwrap()
+                .to_string(),
+            "map(x -> x ^ 2, l)"
+        );
+
+        let mut ctx = context::Context::<Dynamic>::default();
+        let mut calc = |line: &str| {
+            ctx.calc_command(&parser::parse_command(line).unwrap())
+                .map(|(_, value)| value)
+        };
+        assert_eq!(
+            calc("sum(map(x -> x^2, range(1, 10)))").unwrap(),
+            Dynamic::Num(385.0)
+        );
+        assert_eq!(
+            calc("filter(x -> x - 3, range(1, 5))").unwrap(),
+            Dynamic::List(vec![1.0.into(), 2.0.into(), 4.0.into(), 5.0.into()])
+        );
+        assert_eq!(
+            calc("reduce((a, b) -> a * b, range(1, 5))").unwrap(),
+            Dynamic::Num(120.0)
+        );
+        assert_eq!(calc("len(range(1, 0))").unwrap(), Dynamic::Num(0.0));
+        assert!(calc("reduce((a, b) -> a, range(1, 0))").is_err());
+
+        // Lambdas capture the variables they refer to when they are evaluated.
+        let _ = calc("k = 3").unwrap();
+        let _ = calc("f = x -> k * x").unwrap();
+        let _ = calc("k = 100").unwrap();
+        assert_eq!(calc("f(2)").unwrap(), Dynamic::Num(6.0));
+        assert_eq!(calc("map(f, range(1, 2))").unwrap().to_string(), "[3, 6]");
+        let _ = calc("compose = (f, g) -> x -> f(g(x))").unwrap();
+        let _ = calc("h = compose(f, y -> y + 1)").unwrap();
+        assert_eq!(calc("h(1)").unwrap(), Dynamic::Num(6.0));
+
+        // Bodies only see their parameters and the variables bound when they were evaluated.
+        assert!(calc("g = x -> z").is_ok());
+        assert!(calc("g(1)").is_err());
+        assert!(calc("f(1, 2)").is_err());
+        assert!(calc("len(1)").is_err());
+        assert!(calc("-f").is_err());
+
+        // Calls of lambdas nest their bodies, so the depth limit still bounds the stack.
+        let _ = calc("deep = reduce((f, g) -> x -> f(g(x)), map(n -> x -> x + n, range(1, 1000)))")
+            .unwrap();
+        assert!(calc("deep(0)")
+            .unwrap_err()
+            .downcast_ref::<limits::LimitExceeded>()
+            .is_some());
+
+        // Plain numbers have no lambdas.
+        let mut ctx = context::Context::new();
+        assert!(ctx
+            .calc_command(&parser::parse_command("f = x -> x").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_statistics() {
+        use context::Dynamic;
+
+        assert_eq!(
+            parser::parse_command("[1, -2, [x]]").unwrap().to_string(),
+            "[1, -2, [x]]"
+        );
+        assert_eq!(
+            render::latex(&parser::parse_command("[1, x / 2]").unwrap().expression),
+            "\\left[1, \\frac{x}{2}\\right]"
+        );
+
+        let mut ctx = context::Context::<Dynamic>::default();
+        let _ = statistics::register(&mut ctx);
+        let mut calc = |line: &str| {
+            ctx.calc_command(&parser::parse_command(line).unwrap())
+                .map(|(_, value)| value)
+        };
+        let _ = calc("l = [2, 4, 4, 4, 5, 5, 7, 9]").unwrap();
+        let close = |value: Result<Dynamic, _>, expected: f64| match value {
+            Ok(Dynamic::Num(num)) => (num - expected).abs() < 1e-9,
+            _ => false,
+        };
+        assert!(close(calc("mean(l)"), 5.0));
+        assert!(close(calc("median(l)"), 4.5));
+        assert!(close(calc("median([3, 1, 2])"), 2.0));
+        assert!(close(calc("mode(l)"), 4.0));
+        assert!(close(calc("mode([0.5, 0.25, 0.5, 0.25])"), 0.25));
+        assert!(close(calc("variance(l)"), 32.0 / 7.0));
+        assert!(close(calc("stddev(l)"), (32.0_f64 / 7.0).sqrt()));
+        assert!(close(calc("percentile(l, 0)"), 2.0));
+        assert!(close(calc("percentile(l, 50)"), 4.5));
+        assert!(close(calc("percentile(l, 90)"), 7.6));
+        assert!(close(calc("percentile(l, 100)"), 9.0));
+        assert!(close(calc("min(l)"), 2.0));
+        assert!(close(calc("max(map(x -> x / 2, l))"), 4.5));
+
+        assert!(calc("mean([])").is_err());
+        assert!(calc("variance([1])").is_err());
+        assert!(calc("percentile(l, 101)").is_err());
+        assert!(calc("mean(3)").is_err());
+        assert!(calc("mean([1, [2]])").is_err());
+        assert_eq!(statistics::median(&[1.0, 2.0, 3.0, 4.0]), Some(2.5));
+    }
+
+    #[test]
+    fn test_plot() {
+        let ctx = context::Context::new();
+        let expression = |line: &str| parser::parse_command(line).unwrap().expression;
+        let svg_runs = |plot: &plot::Plot| plot.svg(640.0, 400.0).matches('M').count();
+
+        // The pole of `1 / x` splits the graph, while steep continuous graphs stay connected.
+        let pole = plot::Plot::new(&ctx, &expression("1 / x"), "x", -1.0, 1.0).unwrap();
+        assert_eq!(svg_runs(&pole), 2);
+        let (low, high) = pole.y_range();
+        assert!(low < -1.0 && high > 1.0 && high < 1000.0);
+        let steep = plot::Plot::new(&ctx, &expression("x ^ 9"), "x", -3.0, 3.0).unwrap();
+        assert_eq!(svg_runs(&steep), 1);
+        let (low, high) = steep.y_range();
+        assert!(low < -19683.0 && high > 19683.0);
+
+        // Undefined samples are left out.
+        let factorial = plot::Plot::new(&ctx, &expression("x!"), "x", -3.0, 3.0).unwrap();
+        assert!(factorial.points().all(|(x, _)| x >= 0.0));
+
+        let text = steep.render(plot::Style::Braille, 20, 5);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].contains('┤') && lines[4].contains('┤'));
+        assert!(lines[..5]
+            .iter()
+            .all(|line| line.chars().count() == lines[0].chars().count()));
+        assert!(lines[6].trim_start().starts_with("-3"));
+        assert!(lines[6].ends_with('3'));
+        let blocks = steep.render(plot::Style::Block, 20, 5);
+        assert!(blocks.contains('█') || blocks.contains('▀') || blocks.contains('▄'));
+
+        // Empty dimensions are drawn as one character.
+        for (width, height) in [(0, 0), (0, 5), (20, 0), (1, 1)] {
+            for style in [plot::Style::Braille, plot::Style::Block] {
+                let text = steep.render(style, width, height);
+                assert_eq!(text.lines().count(), height.max(1) + 2);
+            }
+        }
+
+        assert!(plot::Plot::new(&ctx, &expression("1 / 0"), "x", -1.0, 1.0).is_err());
+        assert!(plot::Plot::new(&ctx, &expression("x"), "x", 1.0, -1.0).is_err());
+    }
+
+    #[test]
+    fn test_session() {
+        use context::Value;
+        use session::Outcome;
+
+        let mut ctx = context::Context::<context::Dynamic>::default();
+        let mut run =
+            |line: &str| session::run_line(&mut ctx, line, |expression, _| Ok(expression.clone()));
+        let value = |outcome: Outcome<context::Dynamic>| match outcome {
+            Outcome::Calculated { value, .. } => value.as_f64(),
+            _ => None,
+        };
+
+        assert!(matches!(run("  ").unwrap(), Outcome::Blank));
+        assert!(
+            matches!(run(":undo").unwrap(), Outcome::Report(lines) if lines == ["nothing to undo"])
+        );
+        assert!(matches!(run(":mod 7").unwrap(), Outcome::Mode(argument) if argument == "7"));
+        assert!(run(":frobnicate").is_err());
+
+        // `expand` is expanded wherever it is, and evaluated if its variable is bound, except in
+        // functions, whose parameters shadow the variable.
+        assert!(matches!(
+            run("expand((x + 1) ^ 2)").unwrap(),
+            Outcome::Expansion(_)
+        ));
+        assert_eq!(value(run("x = 2").unwrap()), Some(2.0));
+        assert_eq!(value(run("y = expand((x + 1) ^ 2)").unwrap()), Some(9.0));
+        assert_eq!(value(run("expand((x + 1) ^ 2) * 2").unwrap()), Some(18.0));
+        assert_eq!(value(run("f = x -> expand((x + 1) ^ 2)").unwrap()), None);
+        assert_eq!(value(run("f(1)").unwrap()), Some(4.0));
+        assert!(
+            matches!(run(":history y").unwrap(), Outcome::Report(lines) if lines == ["y = expand((x + 1) ^ 2) => 9"])
+        );
+
+        // `plot` draws a whole line.
+        assert!(matches!(
+            run("plot(expand((x - 1) ^ 2), x, 0, 2)").unwrap(),
+            Outcome::Plot(_)
+        ));
+        assert!(run("plot(x, x, 0)").is_err());
+        assert!(run("plot(x, 1, 0, 1)").is_err());
+        assert!(run("1 + plot(x, x, 0, 1)").is_err());
+        assert!(run("p = plot(x, x, 0, 1)").is_err());
+
+        // The journal keeps the line as entered, not as rewritten by `prepare`.
+        let mut ctx = context::Context::<modular::Modular>::default();
+        let _ = modular::register(&mut ctx);
+        let prepare = |expression: &Expression, _: &_| modular::with_modulus(expression, 7);
+        assert!(session::run_line(&mut ctx, " z = 3 * 5 ", prepare).is_ok());
+        assert!(matches!(
+            session::run_line(&mut ctx, ":history z", prepare).unwrap(),
+            Outcome::Report(lines) if lines == ["z = 3 * 5 => 1 (mod 7)"]
+        ));
+    }
+
+    #[test]
+    fn test_compile() {
+        let expression = |line: &str| parser::parse_command(line).unwrap().expression;
+
+        // The compiled function agrees with the interpreter.
+        let source = "x ^ 3 - 2 * x * y + y / 4 - 3! + 50%";
+        let compiled = expression(source).compile(&["x", "y"]).unwrap();
+        let mut ctx = context::Context::new();
+        for (x, y) in [(0.0, 1.0), (1.5, -2.0), (-3.0, 7.25)] {
+            let _ = ctx.set_var("x", x).set_var("y", y);
+            let expected = ctx.calc_expression(&expression(source)).unwrap();
+            assert_eq!(compiled(&[x, y]), expected);
+        }
+
+        // Variables are resolved by position, and failures become NaN.
+        let swapped = expression("x - y").compile(&["y", "x"]).unwrap();
+        assert_eq!(swapped(&[1.0, 10.0]), 9.0);
+        let partial = expression("1 / x + (x - 1)!").compile(&["x"]).unwrap();
+        assert!(partial(&[0.0]).is_nan());
+        assert!(partial(&[0.5]).is_nan());
+        assert_eq!(partial(&[4.0]), 6.25);
+
+        for source in ["x + z", "f(x)", "[x]", "y -> x", "x +/- 1"] {
+            assert!(expression(source).compile(&["x"]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_dag() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let expression = |line: &str| parser::parse_command(line).unwrap().expression;
+
+        // Shared subtrees are stored once, and converting back restores the tree.
+        let mut graph = dag::Dag::new();
+        let source = expression("(x + 1) * (x + 1) + (x + 1) * (x + 1) / 2");
+        let root = graph.insert(&source);
+        assert_eq!(graph.tree_size(root), 17);
+        assert_eq!(graph.size(root), 7);
+        assert_eq!(graph.len(), 7);
+        assert_eq!(graph.to_expression(root), source);
+        let square = graph.insert(&expression("(x + 1) * (x + 1)"));
+        assert_eq!(graph.len(), 7);
+        assert_eq!(graph.size(square), 4);
+
+        // Doubling a term doubles the tree but adds a single node.
+        let mut term = expression("x");
+        for _ in 0..16 {
+            term = Expression::BinOp {
+                op: BinOp::Add,
+                lhs: Box::new(term.clone()),
+                rhs: Box::new(term),
+            };
+        }
+        let mut graph = dag::Dag::new();
+        let root = graph.insert(&term);
+        assert_eq!(graph.size(root), 17);
+        assert_eq!(graph.tree_size(root), (1 << 17) - 1);
+        let mut ctx = context::Context::new();
+        let _ = ctx.set_var("x", 1.0);
+        assert_eq!(graph.calc(root, &ctx).unwrap(), 65536.0);
+
+        // Each shared call is made once.
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let _ = ctx.register_fn("f", 1, move |args| {
+            let _ = counter.fetch_add(1, Ordering::SeqCst);
+            Ok(args[0] * 2.0)
+        });
+        let mut graph = dag::Dag::new();
+        let root = graph.insert(&expression("f(x) * f(x) + f(x)"));
+        assert_eq!(graph.calc(root, &ctx).unwrap(), 6.0);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let unbound = graph.insert(&expression("y + f(x)"));
+        assert!(graph.calc(unbound, &ctx).is_err());
+
+        // Percentages agree with the tree evaluator.
+        for line in ["200 + 10%", "200 - 10% * 2", "200 * 10%", "x - 50%"] {
+            let source = expression(line);
+            let root = graph.insert(&source);
+            assert_eq!(
+                graph.calc(root, &ctx).unwrap(),
+                ctx.calc_expression(&source).unwrap(),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_round_trip() {
+        for seed in 0..2000 {
+            let command = arbitrary::Gen::new(seed).arbitrary::<Command>();
+            let line = command.to_string();
+            assert_eq!(parser::parse_command(&line).unwrap(), command, "{}", line);
+        }
+
+        // Choices can also be taken from a fuzzer's bytes, ending in leaves once they run out.
+        let bytes = (0..=255).rev().collect::<Vec<u8>>();
+        for start in 0..bytes.len() {
+            let command = arbitrary::Gen::from_bytes(&bytes[start..]).arbitrary::<Command>();
+            let line = command.to_string();
+            assert_eq!(parser::parse_command(&line).unwrap(), command, "{}", line);
+        }
+
+        // Arbitrary text is rejected without panicking.
+        let alphabet = [
+            '(', ')', '[', ']', ',', '-', '>', '+', '/', '*', '^', '!', '%', '=', '.', '1', 'e',
+            'x', '$', ' ', '\t', '±', 'é', '\n',
+        ];
+        for seed in 0..2000 {
+            let mut gen = arbitrary::Gen::new(seed);
+            let line = (0..gen.below(24))
+                .map(|_| *gen.choose(&alphabet))
+                .collect::<String>();
+            let _ = parser::parse_command(&line);
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_evaluation() {
+        let limits = limits::Limits {
+            max_steps: 10_000,
+            ..limits::Limits::default()
+        };
+        let mut numbers = context::Context::new();
+        let _ = numbers.set_limits(limits).set_var("x", 1.5);
+        let mut dynamic = context::Context::<context::Dynamic>::default();
+        let _ = statistics::register(dynamic.set_limits(limits)).set_var("y", 2.0.into());
+        let mut uncertain = context::Context::<uncertain::Uncertain>::default();
+        let _ = uncertain.set_limits(limits);
+
+        // Evaluation fails or succeeds, but never panics.
+        for seed in 0..2000 {
+            let command = arbitrary::Gen::new(seed).arbitrary::<Command>();
+            let _ = numbers.calc_command(&command);
+            let _ = dynamic.calc_command(&command);
+            let _ = uncertain.calc_command(&command);
+
+            let expression = &command.expression;
+            if let Ok(compiled) = expression.compile(&["x", "y"]) {
+                let _ = compiled(&[1.5, 2.0]);
+            }
+            let mut graph = dag::Dag::new();
+            let root = graph.insert(expression);
+            let _ = graph.calc(root, &dynamic);
+            let _ = polynomial::expand(expression);
+            let _ = (render::latex(expression), render::mathml(expression));
+        }
+
+        // So are deep inputs, as in the fuzzer's seeds, which are rejected before parsing once they
+        // are too deep.
+        let shapes = [
+            ("[", "1", "]"),
+            ("[", "1", ""),
+            ("-(", "1", ")"),
+            ("-", "1", ""),
+            ("2^", "2", ""),
+            ("x -> ", "x", ""),
+            ("1-", "1", ""),
+            ("", "1", "!"),
+        ];
+        for (prefix, atom, suffix) in shapes {
+            let line = |n| format!("{}{}{}", prefix.repeat(n), atom, suffix.repeat(n));
+            assert!(parser::parse_command(&line(20_000)).is_err());
+
+            if let Ok(command) = parser::parse_command(&line(200)) {
+                let _ = numbers.calc_command(&command);
+                let _ = dynamic.calc_command(&command);
+                let _ = uncertain.calc_command(&command);
+                let expression = &command.expression;
+                let _ = expression.compile(&["x", "y"]);
+                let mut graph = dag::Dag::new();
+                let root = graph.insert(expression);
+                let _ = graph.calc(root, &dynamic);
+                let _ = polynomial::expand(expression);
+                let _ = (render::latex(expression), render::mathml(expression));
+            }
+        }
+    }
+
+    #[test]
+    fn test_precedence_invariants() {
+        fn check(expression: &Expression) {
+            if let Expression::BinOp { op, lhs, rhs } = expression {
+                // Operands bind tighter, or as tight on the side the operator associates to.
+                let (lhs_min, rhs_min) = if op.is_right_assoc() {
+                    (op.precedence() + 1, op.precedence())
+                } else {
+                    (op.precedence(), op.precedence() + 1)
+                };
+                assert!(lhs.precedence() >= lhs_min && rhs.precedence() >= rhs_min);
+            }
+            expression.children().into_iter().for_each(check);
+        }
+
+        // Without parentheses, the tree follows the operator table, so printing it needs none.
+        for seed in 0..2000 {
+            let mut gen = arbitrary::Gen::new(seed);
+            let mut line = if gen.below(2) == 0 { "-" } else { "" }.to_string();
+            for i in 0..=gen.below(6) {
+                if i > 0 {
+                    line.push(' ');
+                    line.push_str(gen.arbitrary::<BinOp>().symbol());
+                    line.push(' ');
+                }
+                line.push_str(*gen.choose(&["1", "2.5", "x", "f(y)", "[3]"]));
+                for _ in 0..gen.below(3) {
+                    line.push_str(*gen.choose(&["!", "%"]));
+                }
+            }
+            let expression = parser::parse_command(&line).unwrap().expression;
+            assert_eq!(expression.to_string(), line);
+            check(&expression);
+        }
+    }
+
+    #[test]
+    fn test_nested_parentheses() {
+        let mut ctx = context::Context::new();
+        let _ = ctx.register_fn("f", 2, |args| Ok(args[0] - args[1]));
+        let calc = |line: &str| {
+            let command = parser::parse_command(line).unwrap();
+            ctx.calc_expression(&command.expression).unwrap()
+        };
+
+        // Each group is parsed on its own, not as the first group of the line.
+        assert_eq!(calc("2 * (3 + 4)"), 14.0);
+        assert_eq!(calc("(1 + 2) * (3 + 4)"), 21.0);
+        assert_eq!(calc("(10 - (2 - (3 - 1))) / ((4))"), 2.5);
+        assert_eq!(calc("f((1 + 1), ((2) * 3)) * (2 ^ (1 + 1))"), -16.0);
+        assert_eq!(
+            parser::parse_command("2 * (3 + 4)").unwrap().expression,
+            Expression::BinOp {
+                op: BinOp::Multiply,
+                lhs: Box::new(Expression::Num(2.0)),
+                rhs: Box::new(Expression::BinOp {
+                    op: BinOp::Add,
+                    lhs: Box::new(Expression::Num(3.0)),
+                    rhs: Box::new(Expression::Num(4.0)),
+                }),
+            }
+        );
+
+        // Redundant parentheses leave no trace, however deep.
+        let deep = format!("{}1 + x{}", "(".repeat(250), ")".repeat(250));
+        assert_eq!(
+            parser::parse_command(&deep).unwrap(),
+            parser::parse_command("1 + x").unwrap()
+        );
+
+        // Groups nested on the right and on the left.
+        let (mut right, mut left) = ("1".to_string(), "1".to_string());
+        let (mut right_value, mut left_value) = (1.0, 1.0);
+        for i in 0..200 {
+            right = format!("{} - ({})", i, right);
+            left = format!("({}) - {}", left, i);
+            right_value = i as f64 - right_value;
+            left_value -= i as f64;
+        }
+        assert_eq!(calc(&right), right_value);
+        assert_eq!(calc(&left), left_value);
+    }
+
+    #[test]
+    fn test_integer() {
+        use crate::assignments::assignment01;
+        use integer::{Int, IntType, Overflow};
+
+        let calc = |overflow: Overflow, default: IntType, lines: &[&str]| {
+            let mut ctx = context::Context::<Int>::default();
+            let _ = integer::register(&mut ctx, overflow);
+            let mut values = Vec::new();
+            for line in lines {
+                let command = parser::parse_command(line).unwrap();
+                let command = Command {
+                    expression: integer::infer_types(&command.expression, default, &ctx)?,
+                    ..command
+                };
+                values.push(ctx.calc_command(&command)?.1);
+            }
+            anyhow::Ok(values)
+        };
+        let value = |overflow, default, line| calc(overflow, default, &[line]).map(|v| v[0]);
+
+        // Suffixes are casts, and unsuffixed literals take the type of the other operand.
+        assert_eq!(
+            parser::parse_command("255u8").unwrap().expression,
+            Expression::Call {
+                name: "u8".to_string(),
+                args: vec![Expression::Num(255.0)],
+            }
+        );
+        let wrapped = value(Overflow::Wrapping, IntType::U32, "255u8 + 1").unwrap();
+        assert_eq!((wrapped.value(), wrapped.ty()), (0, Some(IntType::U8)));
+        assert!(wrapped.overflowed() && context::Value::warning(&wrapped).is_some());
+        let values = calc(Overflow::Wrapping, IntType::U32, &["x = 200u8", "x + 56"]).unwrap();
+        assert_eq!(values[1].to_string(), "0u8");
+        let values = calc(Overflow::Wrapping, IntType::I16, &["4294967295 + 1", "-1"]).unwrap();
+        assert_eq!(
+            (values[0].to_string(), values[1].to_string()),
+            ("0i16".to_string(), "-1i16".to_string())
+        );
+        assert!(value(Overflow::Wrapping, IntType::U32, "1u8 + 1u16").is_err());
+        assert!(value(Overflow::Wrapping, IntType::U32, "1.5").is_err());
+
+        // Literals are exact, or rejected rather than rounded.
+        assert_eq!(
+            value(Overflow::Checked, IntType::U64, "9007199254740991")
+                .unwrap()
+                .value(),
+            (1 << 53) - 1
+        );
+        for line in [
+            "9007199254740993",
+            "18446744073709551615u64",
+            "-9007199254740992i64",
+        ] {
+            assert!(
+                value(Overflow::Wrapping, IntType::U64, line).is_err(),
+                "{}",
+                line
+            );
+        }
+        assert_eq!(
+            value(Overflow::Checked, IntType::U64, "(2 ^ 63 - 1) * 2 + 1")
+                .unwrap()
+                .value(),
+            u64::MAX as i128
+        );
+
+        // Wrapping agrees with assignment 1.
+        for (a, b) in [
+            (usize::MAX, 1),
+            (3, 5),
+            (usize::MAX / 2 + 7, usize::MAX / 2 + 9),
+        ] {
+            let lhs = Int::new(a as i128, IntType::Usize, Overflow::Wrapping).unwrap();
+            let rhs = Int::new(b as i128, IntType::Usize, Overflow::Wrapping).unwrap();
+            let add = context::Value::calc_binop(BinOp::Add, &lhs, &rhs).unwrap();
+            let sub = context::Value::calc_binop(BinOp::Subtract, &lhs, &rhs).unwrap();
+            assert_eq!(add.value(), assignment01::add(a, b) as i128);
+            assert_eq!(sub.value(), assignment01::sub(a, b) as i128);
+        }
+
+        // The three overflow modes.
+        for (line, wrapping, saturating) in [
+            ("127i8 + 1", -128, 127),
+            ("-128i8 - 1", 127, -128),
+            ("-128i8 / -1", -128, 127),
+            ("16i8 * -9", 112, -128),
+            ("3i8 ^ 5", -13, 127),
+            ("(-3i8) ^ 131", -27, -128),
+            ("6i8!", -48, 127),
+        ] {
+            let value = |overflow| value(overflow, IntType::I32, line).map(|v| v.value());
+            assert_eq!(value(Overflow::Wrapping).unwrap(), wrapping, "{}", line);
+            assert_eq!(value(Overflow::Saturating).unwrap(), saturating, "{}", line);
+            assert!(value(Overflow::Checked).is_err(), "{}", line);
+        }
+        assert_eq!(
+            value(Overflow::Checked, IntType::U64, "2 ^ 63 + (2 ^ 63 - 1)")
+                .unwrap()
+                .value(),
+            u64::MAX as i128
+        );
+        assert_eq!(
+            value(Overflow::Wrapping, IntType::U64, "3 ^ 1000")
+                .unwrap()
+                .value(),
+            3_u64.wrapping_pow(1000) as i128
+        );
+        assert_eq!(
+            value(Overflow::Wrapping, IntType::U32, "u8(300)")
+                .unwrap()
+                .value(),
+            44
+        );
+        assert_eq!(
+            value(Overflow::Saturating, IntType::U32, "u8(-5)")
+                .unwrap()
+                .value(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_modular() {
+        use crate::assignments::assignment02::{extended_gcd, gcd};
+        use modular::Modular;
+
+        let calc = |modulus: u64, lines: &[&str]| {
+            let mut ctx = context::Context::<Modular>::default();
+            let _ = modular::register(&mut ctx);
+            let mut values = Vec::new();
+            for line in lines {
+                let command = parser::parse_command(line).unwrap();
+                let command = Command {
+                    expression: modular::with_modulus(&command.expression, modulus)?,
+                    ..command
+                };
+                values.push(ctx.calc_command(&command)?.1);
+            }
+            anyhow::Ok(values)
+        };
+        let value = |modulus, line| calc(modulus, &[line]).map(|v| v[0].to_string());
+        const P: u64 = 1_000_000_007;
+
+        // The extended GCD gives Bezout coefficients.
+        for (a, b) in [
+            (240, 46),
+            (17, 5),
+            (0, 9),
+            (9, 0),
+            (P, 2),
+            (u64::MAX, u64::MAX - 1),
+        ] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a as i128 * x + b as i128 * y, g as i128);
+        }
+        assert_eq!(modular::mod_inverse(3, 7), Some(5));
+        assert_eq!(modular::mod_inverse(4, 8), None);
+
+        // Every operation reduces modulo n.
+        assert_eq!(value(7, "3 / 2").unwrap(), "5 (mod 7)");
+        assert_eq!(value(7, "2 - 5").unwrap(), "4 (mod 7)");
+        assert_eq!(value(7, "-3 * 10").unwrap(), "5 (mod 7)");
+        assert_eq!(value(P, "6!").unwrap(), "720 (mod 1000000007)");
+        assert_eq!(value(P, "1 / 2 * 2").unwrap(), "1 (mod 1000000007)");
+        assert!(value(6, "1 / 4").is_err());
+        assert!(value(7, "1 / 0").is_err());
+        assert!(value(7, "1.5").is_err());
+
+        // Exponents are exact integers, so that Fermat's little theorem holds.
+        assert_eq!(value(P, "2 ^ 1000000006").unwrap(), "1 (mod 1000000007)");
+        assert_eq!(
+            value(P, "3 ^ (10 ^ 30)").unwrap(),
+            format!("{} (mod {})", modular::mod_pow(3, 10_u128.pow(30), P), P)
+        );
+        assert_eq!(value(7, "2 ^ 7").unwrap(), "2 (mod 7)");
+        assert_eq!(value(7, "3 ^ -1").unwrap(), "5 (mod 7)");
+        assert!(value(8, "2 ^ -1").is_err());
+        assert!(value(7, "2 ^ 0.5").is_err());
+        assert!(value(7, "2 ^ (1 / 2 + 0.5)").is_err());
+
+        // Literals above 2^53 would be rounded, so they are rejected.
+        assert!(value(P, "123456789123456789123 * 1").is_err());
+        assert!(value(P, "2 ^ 123456789123456789123").is_err());
+        assert_eq!(
+            value(P, "123456789 * 10 ^ 12 + 123456789123").unwrap(),
+            format!(
+                "{} (mod {})",
+                123_456_789_123_456_789_123_u128 % P as u128,
+                P
+            )
+        );
+
+        // Variables keep their residues, and `mod` mixes moduli only explicitly.
+        let values = calc(P, &["x = 10 ^ 9", "x * x", "y = x + 8"]).unwrap();
+        assert_eq!(values[1].to_string(), "49 (mod 1000000007)");
+        assert_eq!(values[2], Modular::residue(1, P));
+        assert_eq!(value(P, "mod(10, 3) * mod(2, 3)").unwrap(), "2 (mod 3)");
+        assert!(value(P, "mod(10, 3) + 1").is_err());
+    }
+
+    #[test]
+    fn test_calendar() {
+        use crate::assignments::assignment03::{next_weekday, DayOfWeek};
+        use calendar::{Date, Duration};
+
+        let mut ctx = context::Context::<context::Dynamic>::default();
+        let _ = calendar::register(&mut ctx);
+        let mut calc = |line: &str| {
+            ctx.calc_command(&parser::parse_command(line).unwrap())
+                .map(|(_, value)| value.to_string())
+        };
+
+        // Literals are calls, and other digits are arithmetic.
+        assert_eq!(
+            parser::parse_command("2026-10-18 + 3 weeks")
+                .unwrap()
+                .to_string(),
+            "date(2026, 10, 18) + weeks(3)"
+        );
+        assert_eq!(
+            parser::parse_command("2026-10-18T09:30 - 1.5hour")
+                .unwrap()
+                .to_string(),
+            "datetime(2026, 10, 18, 9, 30, 0) - hours(1.5)"
+        );
+        assert_eq!(
+            parser::parse_command("2026 - 10 - 18").unwrap().to_string(),
+            "2026 - 10 - 18"
+        );
+        assert_eq!(
+            parser::parse_command("2026-10-189").unwrap().to_string(),
+            "2026 - 10 - 189"
+        );
+
+        assert_eq!(calc("2026-10-18 + 3 weeks").unwrap(), "2026-11-08");
+        assert_eq!(calc("2024-02-28 + 1 day").unwrap(), "2024-02-29");
+        assert_eq!(calc("2023-02-28 + 1 day").unwrap(), "2023-03-01");
+        assert_eq!(
+            calc("2026-10-18T09:30 - 10 hours").unwrap(),
+            "2026-10-17T23:30:00"
+        );
+        assert_eq!(calc("2027-01-01 - 2026-10-18").unwrap(), "10 weeks 5 days");
+        assert_eq!(
+            calc("-(1 day + 90 minutes) / 2").unwrap(),
+            "-12 hours -45 minutes"
+        );
+        assert_eq!(calc("1 week / 1 day").unwrap(), "7");
+        assert_eq!(calc("weekday(2026-12-25)").unwrap(), "Fri");
+        assert_eq!(calc("days_between(2026-10-18, 2027-01-01)").unwrap(), "75");
+        assert_eq!(
+            calc("days_between(2027-01-01T23:00, 2026-10-18)").unwrap(),
+            "-75"
+        );
+        assert!(calc("2026-02-29").is_err());
+        assert!(calc("2026-10-18 + 2026-10-18").is_err());
+        assert!(calc("9999-12-31 + 1 day").is_err());
+        assert!(calc("weekday(3)").is_err());
+
+        // Business days skip weekends, as `next_weekday` does.
+        assert_eq!(
+            calc("add_business_days(2026-10-16, 3)").unwrap(),
+            "2026-10-21"
+        );
+        assert_eq!(
+            calc("add_business_days(2026-10-18, 1)").unwrap(),
+            "2026-10-19"
+        );
+        assert_eq!(
+            calc("add_business_days(2026-10-16, 10)").unwrap(),
+            "2026-10-30"
+        );
+        assert_eq!(
+            calc("business_days_between(2026-10-16, 2026-10-30)").unwrap(),
+            "10"
+        );
+        assert!(calc("add_business_days(2026-10-16, -1)").is_err());
+        let (start, day) = (
+            Date::new(2026, 10, 1).unwrap(),
+            Duration::new(1.0, 86400).unwrap(),
+        );
+        for offset in 0..14 {
+            let date = start
+                .after(Duration::new(offset as f64, 86400).unwrap())
+                .unwrap();
+            let mut expected = date;
+            for count in 0..30 {
+                assert_eq!(date.add_business_days(count).unwrap(), expected);
+                if !matches!(date.weekday(), DayOfWeek::Sat | DayOfWeek::Sun) {
+                    assert_eq!(date.business_days_between(expected), count as i64);
+                }
+                let next = next_weekday(expected.weekday());
+                loop {
+                    expected = expected.after(day).unwrap();
+                    if expected.weekday() == next {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_finance() {
+        let mut ctx = context::Context::<context::Dynamic>::default();
+        let _ = finance::register(&mut ctx);
+        let mut calc = |line: &str| match ctx.calc_command(&parser::parse_command(line).unwrap())?.1
+        {
+            context::Dynamic::Num(num) => Ok(num),
+            value => Err(anyhow::anyhow!("{} is not a number", value)),
+        };
+        let close = |value: f64, expected: f64| (value - expected).abs() < 5e-3;
+
+        // Payments, and the amortization table of a 30-year loan of 200000 at 6%.
+        let payment = calc("pmt(6% / 12, 360, 200000)").unwrap();
+        assert!(close(payment, -1199.10));
+        let balance = |month: f64| -finance::fv(0.005, month, payment, 200000.0);
+        for (month, interest, principal, remaining) in [
+            (1.0, 1000.00, 199.10, 199800.90),
+            (2.0, 999.00, 200.10, 199600.80),
+            (12.0, 988.77, 210.33, 197543.98),
+            (180.0, 712.92, 486.18, 142097.69),
+            (360.0, 5.97, 1193.14, 0.0),
+        ] {
+            let previous = balance(month - 1.0);
+            assert!(close(previous * 0.005, interest));
+            assert!(close(-payment - previous * 0.005, principal));
+            assert!(close(balance(month), remaining));
+        }
+
+        // Spreadsheet examples.
+        assert!(close(calc("pmt(8% / 12, 10, 10000)").unwrap(), -1037.03));
+        assert!(close(calc("fv(12% / 12, 12, -1000)").unwrap(), 12682.50));
+        assert!(close(calc("pv(8% / 12, 12 * 20, 500)").unwrap(), -59777.15));
+        assert!(close(calc("pmt(0, 10, 1000)").unwrap(), -100.0));
+        assert!(close(
+            calc("npv(10%, [-10000, 3000, 4200, 6800])").unwrap(),
+            1188.44
+        ));
+        let flows = "[-70000, 12000, 15000, 18000, 21000";
+        assert!(close(
+            calc(&format!("irr({}, 26000])", flows)).unwrap(),
+            0.0866
+        ));
+        assert!(close(calc(&format!("irr({}])", flows)).unwrap(), -0.0212));
+        // The rate of return makes the net present value zero.
+        let rate = calc("irr([-100, 0, 0, 0, 0, 0, 0, 0, 0, 1e9])").unwrap();
+        assert!(close(rate, 10_f64.powf(7.0 / 9.0) - 1.0));
+        assert!(calc("irr([1, 2, 3])").is_err());
+        assert!(calc("npv(-100%, [1])").is_err());
+        assert!(calc("pmt([1], 2, 3)").is_err());
+    }
+
+    #[test]
+    fn test_sheet() {
+        use sheet::{CellError, CellRef, Evaluated, Sheet};
+
+        let cell = |name: &str| CellRef::from_name(name).unwrap();
+        assert_eq!(cell("B3"), CellRef { row: 2, column: 1 });
+        assert_eq!(cell("AA10").to_string(), "AA10");
+        assert_eq!(CellRef::from_name("ZZ1").unwrap().column, 701);
+        assert!(CellRef::from_name("A0").is_none() && CellRef::from_name("a1").is_none());
+        assert_eq!(
+            sheet::parse_csv("a,\"b,\"\"c\"\"\"\r\n,1\n").unwrap(),
+            vec![vec!["a", "b,\"c\""], vec!["", "1"]]
+        );
+        assert!(sheet::parse_csv("\"a").is_err());
+
+        let mut ctx = context::Context::<context::Dynamic>::default();
+        let _ = statistics::register(&mut ctx);
+        let csv = "Price,Quantity,Total\n\
+                   2.5,4,=A2 * B2\n\
+                   10,=B2 / 2,=A3 * B3\n\
+                   ,,\"=sum(C2:C3) + 10%\"\n\
+                   =mean(A2:B3),=A1 + 1,=C4 - D4\n\
+                   =B6,=C6,=A6\n\
+                   =A6 + 1,=1 / 0,=A1\n";
+        let sheet = Sheet::from_csv(csv).unwrap();
+        let values = sheet.evaluate(&ctx);
+        let value = |name: &str| {
+            let name = cell(name);
+            values[name.row][name.column].clone()
+        };
+        assert_eq!(value("C2"), Evaluated::Value(context::Dynamic::Num(10.0)));
+        assert_eq!(value("C3").to_string(), "20");
+        assert_eq!(value("C4").to_string(), "33");
+        assert_eq!(value("A5").to_string(), "4.625");
+        assert_eq!(value("B5"), Evaluated::Error(CellError::Text(cell("A1"))));
+        assert_eq!(value("C5").to_string(), "33");
+        let cycle = CellError::Cycle(vec![cell("A6"), cell("B6"), cell("C6")]);
+        assert_eq!(value("A6"), Evaluated::Error(cycle.clone()));
+        assert_eq!(value("C6"), Evaluated::Error(cycle.clone()));
+        assert_eq!(value("A7"), Evaluated::Error(cycle.clone()));
+        assert_eq!(cycle.to_string(), "circular reference A6 -> B6 -> C6 -> A6");
+        assert_eq!(value("B7").to_string(), "#ERROR!");
+        assert_eq!(value("C7").to_string(), "#VALUE!");
+        assert_eq!(
+            sheet::write_csv(&values)
+                .lines()
+                .take(4)
+                .collect::<Vec<_>>(),
+            vec!["Price,Quantity,Total", "2.5,4,10", "10,2,20", ",,33"]
+        );
+
+        // Self references, and long chains evaluated without recursion.
+        let values = Sheet::from_csv("=A1 + 1").unwrap().evaluate(&ctx);
+        assert_eq!(values[0][0].to_string(), "#CYCLE!");
+        let csv = (1..10_000)
+            .map(|row| format!("=A{} + 1\n", row))
+            .collect::<String>();
+        let csv = format!("1\n{}", csv);
+        let values = Sheet::from_csv(&csv).unwrap().evaluate(&ctx);
+        assert_eq!(values[9_999][0].to_string(), "10000");
+    }
 }
@@ -5,7 +5,9 @@
 //! You should fill out the `todo!()` placeholders in such a way that `/scripts/grade-06.sh` works fine.
 //! See `assignment06_grade.rs` and `/scripts/grade-06.sh` for the test script.
 
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::BTreeMap, fmt::Debug, num::Wrapping, ops};
+
+pub mod multiplication;
 
 /// Semiring.
 ///
@@ -19,6 +21,98 @@ pub trait Semiring: Debug + Clone + PartialEq {
     fn add(&self, rhs: &Self) -> Self;
     /// Multiplication operation.
     fn mul(&self, rhs: &Self) -> Self;
+
+    /// Multiplies polynomials given by their coefficients in increasing degree order.
+    ///
+    /// Coefficients with faster algorithms than [`multiplication::schoolbook`] override it.
+    fn mul_coefficients(lhs: &[Self], rhs: &[Self]) -> Vec<Self> {
+        multiplication::schoolbook(lhs, rhs)
+    }
+}
+
+/// Ring: a semiring with additive inverses.
+///
+/// Consult <https://en.wikipedia.org/wiki/Ring_(mathematics)>.
+pub trait Ring: Semiring {
+    /// Additive inverse.
+    fn neg(&self) -> Self;
+    /// Subtraction operation.
+    fn sub(&self, rhs: &Self) -> Self {
+        self.add(&rhs.neg())
+    }
+}
+
+/// Field: a ring in which every nonzero element has a multiplicative inverse.
+///
+/// Consult <https://en.wikipedia.org/wiki/Field_(mathematics)>.
+pub trait Field: Ring {
+    /// Multiplicative inverse, or `None` for zero.
+    fn inv(&self) -> Option<Self>;
+    /// Division operation, or `None` if `rhs` is zero.
+    fn div(&self, rhs: &Self) -> Option<Self> {
+        rhs.inv().map(|inv| self.mul(&inv))
+    }
+}
+
+/// Euclidean domain: a ring with division with remainder.
+///
+/// Consult <https://en.wikipedia.org/wiki/Euclidean_domain>.
+pub trait EuclideanDomain: Ring {
+    /// Returns the quotient `q` and the remainder `r` such that `self = q * rhs + r`, where `r` is
+    /// smaller than `rhs`, or `None` if `rhs` is zero.
+    fn div_rem(&self, rhs: &Self) -> Option<(Self, Self)>;
+}
+
+/// Returns the quotient and the remainder of `lhs` by the nonzero `rhs`, or `None` if `rhs` divides
+/// `lhs`.
+///
+/// Division by a unit `1` or `-1` is skipped, as its quotient may overflow, e.g. `i64::MIN / -1`.
+fn euclidean_step<T: EuclideanDomain>(lhs: &T, rhs: &T) -> Option<(T, T)> {
+    if *rhs == T::one() || *rhs == T::one().neg() {
+        return None;
+    }
+    let (q, r) = lhs
+        .div_rem(rhs)
+        .expect("the divisor is nonzero and not a unit");
+    if r == T::zero() {
+        None
+    } else {
+        Some((q, r))
+    }
+}
+
+/// Returns a greatest common divisor by the Euclidean algorithm, which generalizes
+/// [`assignment02::gcd`](crate::assignments::assignment02::gcd) to Euclidean domains.
+///
+/// It is unique up to a unit factor, e.g. the sign of an integer.
+pub fn gcd<T: EuclideanDomain>(lhs: &T, rhs: &T) -> T {
+    let (mut a, mut b) = (lhs.clone(), rhs.clone());
+    if b == T::zero() {
+        return a;
+    }
+    while let Some((_, r)) = euclidean_step(&a, &b) {
+        (a, b) = (b, r);
+    }
+    b
+}
+
+/// Returns `(g, x, y)` where `g` is the [`gcd`] of `lhs` and `rhs`, and `lhs * x + rhs * y = g`.
+///
+/// The algorithm stops at the divisor that leaves no remainder, without computing the cofactors
+/// after it, which are `rhs / g` and `lhs / g` up to sign and may overflow, e.g. for `i64::MIN`.
+pub fn extended_gcd<T: EuclideanDomain>(lhs: &T, rhs: &T) -> (T, T, T) {
+    let (mut a, mut b) = (lhs.clone(), rhs.clone());
+    if b == T::zero() {
+        return (a, T::one(), T::zero());
+    }
+    let (mut x, mut next_x) = (T::one(), T::zero());
+    let (mut y, mut next_y) = (T::zero(), T::one());
+    while let Some((q, r)) = euclidean_step(&a, &b) {
+        (a, b) = (b, r);
+        (x, next_x) = (next_x.clone(), x.sub(&q.mul(&next_x)));
+        (y, next_y) = (next_y.clone(), y.sub(&q.mul(&next_y)));
+    }
+    (b, next_x, next_y)
 }
 
 /// Converts integer to semiring value.
@@ -33,6 +127,18 @@ pub fn from_usize<T: Semiring>(value: usize) -> T {
     result
 }
 
+/// Converts signed integer to ring value.
+pub fn from_isize<T: Ring>(value: isize) -> T {
+    let result = from_usize::<T>(value.unsigned_abs());
+    if value < 0 {
+        result.neg()
+    } else {
+        result
+    }
+}
+
+/// Arithmetic wraps around, i.e., is modulo 2^64 as that of [`Wrapping<u64>`], so that sparse and
+/// dense polynomials overflow alike.
 impl Semiring for u64 {
     fn zero() -> Self {
         0_u64
@@ -42,6 +148,35 @@ impl Semiring for u64 {
         1_u64
     }
 
+    fn add(&self, rhs: &Self) -> Self {
+        self.wrapping_add(*rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Self {
+        self.wrapping_mul(*rhs)
+    }
+
+    /// Multiplies by Karatsuba's algorithm in [`Wrapping`] arithmetic, whose subtractions are exact
+    /// modulo 2^64.
+    fn mul_coefficients(lhs: &[Self], rhs: &[Self]) -> Vec<Self> {
+        let wrapping = |values: &[u64]| values.iter().copied().map(Wrapping).collect::<Vec<_>>();
+        multiplication::karatsuba(&wrapping(lhs), &wrapping(rhs))
+            .into_iter()
+            .map(|Wrapping(value)| value)
+            .collect()
+    }
+}
+
+/// Integers modulo 2^64.
+impl Semiring for Wrapping<u64> {
+    fn zero() -> Self {
+        Wrapping(0)
+    }
+
+    fn one() -> Self {
+        Wrapping(1)
+    }
+
     fn add(&self, rhs: &Self) -> Self {
         self + rhs
     }
@@ -49,6 +184,20 @@ impl Semiring for u64 {
     fn mul(&self, rhs: &Self) -> Self {
         self * rhs
     }
+
+    fn mul_coefficients(lhs: &[Self], rhs: &[Self]) -> Vec<Self> {
+        multiplication::karatsuba(lhs, rhs)
+    }
+}
+
+impl Ring for Wrapping<u64> {
+    fn neg(&self) -> Self {
+        -self
+    }
+
+    fn sub(&self, rhs: &Self) -> Self {
+        self - rhs
+    }
 }
 
 impl Semiring for i64 {
@@ -67,6 +216,30 @@ impl Semiring for i64 {
     fn mul(&self, rhs: &Self) -> Self {
         self * rhs
     }
+
+    fn mul_coefficients(lhs: &[Self], rhs: &[Self]) -> Vec<Self> {
+        multiplication::karatsuba(lhs, rhs)
+    }
+}
+
+impl Ring for i64 {
+    fn neg(&self) -> Self {
+        -self
+    }
+
+    fn sub(&self, rhs: &Self) -> Self {
+        self - rhs
+    }
+}
+
+impl EuclideanDomain for i64 {
+    /// The remainder is non-negative. Overflowing quotients, i.e., `i64::MIN / -1`, are `None`.
+    fn div_rem(&self, rhs: &Self) -> Option<(Self, Self)> {
+        Some((
+            self.checked_div_euclid(*rhs)?,
+            self.checked_rem_euclid(*rhs)?,
+        ))
+    }
 }
 
 impl Semiring for f64 {
@@ -85,8 +258,141 @@ impl Semiring for f64 {
     fn mul(&self, rhs: &Self) -> Self {
         self * rhs
     }
+
+    fn mul_coefficients(lhs: &[Self], rhs: &[Self]) -> Vec<Self> {
+        if lhs.len().min(rhs.len()) < multiplication::TRANSFORM_THRESHOLD {
+            multiplication::karatsuba(lhs, rhs)
+        } else {
+            multiplication::fft(lhs, rhs)
+        }
+    }
+}
+
+impl Ring for f64 {
+    fn neg(&self) -> Self {
+        -self
+    }
+
+    fn sub(&self, rhs: &Self) -> Self {
+        self - rhs
+    }
+}
+
+impl Field for f64 {
+    fn inv(&self) -> Option<Self> {
+        if *self == 0_f64 {
+            None
+        } else {
+            Some(1_f64 / self)
+        }
+    }
+
+    fn div(&self, rhs: &Self) -> Option<Self> {
+        if *rhs == 0_f64 {
+            None
+        } else {
+            Some(self / rhs)
+        }
+    }
+}
+
+/// Integers modulo `M`, which form a field if `M` is prime.
+///
+/// `M` is at least 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModInt<const M: u64>(u64);
+
+impl<const M: u64> ModInt<M> {
+    /// Returns the residue of `value` modulo `M`.
+    pub fn new(value: u64) -> Self {
+        Self(value % M)
+    }
+
+    /// Returns the residue in `0..M`.
+    pub fn value(self) -> u64 {
+        self.0
+    }
+}
+
+impl<const M: u64> Semiring for ModInt<M> {
+    fn zero() -> Self {
+        Self(0)
+    }
+
+    fn one() -> Self {
+        Self::new(1)
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        Self(((self.0 as u128 + rhs.0 as u128) % M as u128) as u64)
+    }
+
+    fn mul(&self, rhs: &Self) -> Self {
+        Self((self.0 as u128 * rhs.0 as u128 % M as u128) as u64)
+    }
+
+    fn mul_coefficients(lhs: &[Self], rhs: &[Self]) -> Vec<Self> {
+        if lhs.len().min(rhs.len()) >= multiplication::TRANSFORM_THRESHOLD {
+            if let Some(product) = multiplication::ntt(lhs, rhs) {
+                return product;
+            }
+        }
+        multiplication::karatsuba(lhs, rhs)
+    }
+}
+
+impl<const M: u64> Ring for ModInt<M> {
+    fn neg(&self) -> Self {
+        Self((M - self.0) % M)
+    }
+}
+
+impl<const M: u64> Field for ModInt<M> {
+    /// By the extended Euclidean algorithm, which also finds no inverse of zero divisors if `M` is
+    /// not prime.
+    fn inv(&self) -> Option<Self> {
+        let (mut a, mut b) = (self.0 as i128, M as i128);
+        let (mut x, mut next_x) = (1_i128, 0_i128);
+        while b != 0 {
+            let q = a / b;
+            (a, b) = (b, a - q * b);
+            (x, next_x) = (next_x, x - q * next_x);
+        }
+        if a != 1 {
+            return None;
+        }
+        Some(Self(x.rem_euclid(M as i128) as u64))
+    }
 }
 
+impl<const M: u64> ops::Add for ModInt<M> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Semiring::add(&self, &rhs)
+    }
+}
+
+impl<const M: u64> ops::Sub for ModInt<M> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Ring::sub(&self, &rhs)
+    }
+}
+
+impl<const M: u64> ops::Mul for ModInt<M> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Semiring::mul(&self, &rhs)
+    }
+}
+
+/// Inverse of the fraction of nonzero coefficients from which polynomials are multiplied as dense
+/// ones.
+const DENSITY: u64 = 4;
+
 /// Polynomials with coefficient in `C`.
 ///
 /// For example, polynomial `x^2 + 5x + 6` is represented in `Polynomial<u64>` as follows:
@@ -94,28 +400,28 @@ impl Semiring for f64 {
 /// ```
 /// Polynomial {
 ///     coefficients: {
-///         2: 1,
-///         1: 5,
 ///         0: 6,
+///         1: 5,
+///         2: 1,
 ///     },
 /// }
 /// ```
+///
+/// The coefficients are ordered by degree, and zero coefficients are not stored.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Polynomial<C: Semiring> {
-    coefficients: HashMap<u64, C>,
+    coefficients: BTreeMap<u64, C>,
 }
 
 impl<C: Semiring> Semiring for Polynomial<C> {
     fn zero() -> Self {
         Self {
-            coefficients: HashMap::new(),
+            coefficients: BTreeMap::new(),
         }
     }
 
     fn one() -> Self {
-        let mut map = HashMap::new();
-        let _unuse = map.insert(0_u64, C::one());
-        Self { coefficients: map }
+        Self::monomial(C::one(), 0)
     }
 
     fn add(&self, rhs: &Self) -> Self {
@@ -128,54 +434,295 @@ impl<C: Semiring> Semiring for Polynomial<C> {
         Self { coefficients: map }
     }
 
+    /// Dense operands are multiplied by [`Semiring::mul_coefficients`], and sparse ones term by
+    /// term.
     fn mul(&self, rhs: &Self) -> Self {
-        let mut pol = Self {
-            coefficients: HashMap::new(),
-        };
+        if let (Some(lhs), Some(rhs)) = (self.dense(), rhs.dense()) {
+            let product = C::mul_coefficients(&lhs.1, &rhs.1);
+            return Self::from_dense(lhs.0 + rhs.0, product);
+        }
+
+        let mut map = BTreeMap::new();
         for (k, v) in &rhs.coefficients {
-            let mut inner_map = HashMap::new();
-            for key in self.coefficients.keys() {
-                let entry = self.coefficients.get(key).unwrap();
-                let _unuse = inner_map.insert(k + key, v.mul(entry));
+            for (key, entry) in &self.coefficients {
+                let product = map.entry(k + key).or_insert_with(C::zero);
+                *product = product.add(&v.mul(entry));
+            }
+        }
+        map.retain(|_, v| *v != C::zero());
+        Self { coefficients: map }
+    }
+}
+
+impl<C: Ring> Ring for Polynomial<C> {
+    fn neg(&self) -> Self {
+        Self {
+            coefficients: self
+                .coefficients
+                .iter()
+                .map(|(k, v)| (*k, v.neg()))
+                .collect(),
+        }
+    }
+}
+
+/// Long division, whose remainder has a lower degree than the divisor.
+impl<C: Field> EuclideanDomain for Polynomial<C> {
+    fn div_rem(&self, rhs: &Self) -> Option<(Self, Self)> {
+        let (degree, leading) = rhs.leading()?;
+        let inverse = leading.inv()?;
+        let (mut quotient, mut remainder) = (Self::zero(), self.clone());
+        while let Some((remainder_degree, remainder_leading)) = remainder.leading() {
+            if remainder_degree < degree {
+                break;
             }
-            pol = pol.add(&Self {
-                coefficients: inner_map,
-            });
+            let term = Self::monomial(remainder_leading.mul(&inverse), remainder_degree - degree);
+            quotient = quotient.add(&term);
+            remainder = remainder.sub(&term.mul(rhs));
+            // The leading terms cancel, even if rounding says otherwise.
+            let _unused = remainder.coefficients.remove(&remainder_degree);
         }
-        pol.coefficients.retain(|_, v| *v != C::zero());
-        pol
+        Some((quotient, remainder))
     }
 }
 
 impl<C: Semiring> From<C> for Polynomial<C> {
     fn from(value: C) -> Self {
-        let mut map = HashMap::new();
-        let _unuse = map.insert(0_u64, value);
-        Self { coefficients: map }
+        Self::monomial(value, 0)
+    }
+}
+
+/// Implements the binary operator `$trait` of polynomials by `$method`, for owned and borrowed
+/// operands, where either operand may be a polynomial and the right one may be a coefficient.
+macro_rules! impl_polynomial_op {
+    ($bound:ident, $trait:ident, $fn:ident, $method:path) => {
+        impl<C: $bound> ops::$trait<&Polynomial<C>> for &Polynomial<C> {
+            type Output = Polynomial<C>;
+
+            fn $fn(self, rhs: &Polynomial<C>) -> Polynomial<C> {
+                $method(self, rhs)
+            }
+        }
+
+        impl<C: $bound> ops::$trait<Polynomial<C>> for &Polynomial<C> {
+            type Output = Polynomial<C>;
+
+            fn $fn(self, rhs: Polynomial<C>) -> Polynomial<C> {
+                $method(self, &rhs)
+            }
+        }
+
+        impl<C: $bound> ops::$trait<&Polynomial<C>> for Polynomial<C> {
+            type Output = Polynomial<C>;
+
+            fn $fn(self, rhs: &Polynomial<C>) -> Polynomial<C> {
+                $method(&self, rhs)
+            }
+        }
+
+        impl<C: $bound> ops::$trait<Polynomial<C>> for Polynomial<C> {
+            type Output = Polynomial<C>;
+
+            fn $fn(self, rhs: Polynomial<C>) -> Polynomial<C> {
+                $method(&self, &rhs)
+            }
+        }
+
+        impl<C: $bound> ops::$trait<C> for &Polynomial<C> {
+            type Output = Polynomial<C>;
+
+            fn $fn(self, rhs: C) -> Polynomial<C> {
+                $method(self, &Polynomial::from(rhs))
+            }
+        }
+
+        impl<C: $bound> ops::$trait<C> for Polynomial<C> {
+            type Output = Polynomial<C>;
+
+            fn $fn(self, rhs: C) -> Polynomial<C> {
+                $method(&self, &Polynomial::from(rhs))
+            }
+        }
+    };
+}
+
+impl_polynomial_op!(Semiring, Add, add, Semiring::add);
+impl_polynomial_op!(Semiring, Mul, mul, Semiring::mul);
+impl_polynomial_op!(Ring, Sub, sub, Ring::sub);
+
+/// Implements the binary operator `$trait` of the coefficient `$coefficient` on the left and a
+/// polynomial on the right by `$method`, e.g. `5 * x`, which a generic implementation cannot.
+macro_rules! impl_coefficient_op {
+    ($trait:ident, $fn:ident, $method:path, $coefficient:ty $(, $param:ident)?) => {
+        impl$(<const $param: u64>)? ops::$trait<&Polynomial<$coefficient>> for $coefficient {
+            type Output = Polynomial<$coefficient>;
+
+            fn $fn(self, rhs: &Polynomial<$coefficient>) -> Polynomial<$coefficient> {
+                $method(&Polynomial::from(self), rhs)
+            }
+        }
+
+        impl$(<const $param: u64>)? ops::$trait<Polynomial<$coefficient>> for $coefficient {
+            type Output = Polynomial<$coefficient>;
+
+            fn $fn(self, rhs: Polynomial<$coefficient>) -> Polynomial<$coefficient> {
+                $method(&Polynomial::from(self), &rhs)
+            }
+        }
+    };
+}
+
+impl_coefficient_op!(Add, add, Semiring::add, u64);
+impl_coefficient_op!(Mul, mul, Semiring::mul, u64);
+impl_coefficient_op!(Add, add, Semiring::add, i64);
+impl_coefficient_op!(Mul, mul, Semiring::mul, i64);
+impl_coefficient_op!(Sub, sub, Ring::sub, i64);
+impl_coefficient_op!(Add, add, Semiring::add, f64);
+impl_coefficient_op!(Mul, mul, Semiring::mul, f64);
+impl_coefficient_op!(Sub, sub, Ring::sub, f64);
+impl_coefficient_op!(Add, add, Semiring::add, ModInt<M>, M);
+impl_coefficient_op!(Mul, mul, Semiring::mul, ModInt<M>, M);
+impl_coefficient_op!(Sub, sub, Ring::sub, ModInt<M>, M);
+
+impl<C: Ring> ops::Neg for &Polynomial<C> {
+    type Output = Polynomial<C>;
+
+    fn neg(self) -> Polynomial<C> {
+        Ring::neg(self)
+    }
+}
+
+impl<C: Ring> ops::Neg for Polynomial<C> {
+    type Output = Polynomial<C>;
+
+    fn neg(self) -> Polynomial<C> {
+        Ring::neg(&self)
     }
 }
 
 impl<C: Semiring> Polynomial<C> {
     /// Constructs polynomial `x`.
     pub fn x() -> Self {
-        let mut map = HashMap::new();
-        let _unused = map.insert(1_u64, C::one());
+        Self::monomial(C::one(), 1)
+    }
+
+    /// Constructs polynomial `coefficient * x^degree`.
+    pub fn monomial(coefficient: C, degree: u64) -> Self {
+        let mut map = BTreeMap::new();
+        if coefficient != C::zero() {
+            let _unused = map.insert(degree, coefficient);
+        }
         Self { coefficients: map }
     }
 
+    /// Returns the lowest degree and the coefficients from it to the degree, or `None` if fewer
+    /// than `1 / DENSITY` of them are nonzero.
+    fn dense(&self) -> Option<(u64, Vec<C>)> {
+        let (low, _) = self.terms().next()?;
+        let (high, _) = self.leading()?;
+        let len = high - low + 1;
+        if len > DENSITY * self.coefficients.len() as u64 {
+            return None;
+        }
+        let mut dense = vec![C::zero(); len as usize];
+        for (degree, coefficient) in self.terms() {
+            dense[(degree - low) as usize] = coefficient.clone();
+        }
+        Some((low, dense))
+    }
+
+    /// Constructs `x^low` times the polynomial with the coefficients in increasing degree order.
+    fn from_dense(low: u64, coefficients: Vec<C>) -> Self {
+        Self {
+            coefficients: (low..)
+                .zip(coefficients)
+                .filter(|(_, coefficient)| *coefficient != C::zero())
+                .collect(),
+        }
+    }
+
+    /// Returns the degree and the coefficient of the highest nonzero term, or `None` for zero.
+    fn leading(&self) -> Option<(u64, &C)> {
+        self.terms().next_back()
+    }
+
+    /// Returns the degree, or `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<u64> {
+        self.leading().map(|(degree, _)| degree)
+    }
+
+    /// Returns the coefficient of the highest degree term, or `None` for the zero polynomial.
+    pub fn leading_coefficient(&self) -> Option<&C> {
+        self.leading().map(|(_, coefficient)| coefficient)
+    }
+
+    /// Returns the coefficient of `x^degree`.
+    pub fn coefficient(&self, degree: u64) -> C {
+        self.coefficients
+            .get(&degree)
+            .cloned()
+            .unwrap_or_else(C::zero)
+    }
+
+    /// Returns the nonzero terms as `(degree, coefficient)` pairs in increasing degree order.
+    pub fn terms(&self) -> impl DoubleEndedIterator<Item = (u64, &C)> {
+        self.coefficients.iter().map(|(k, v)| (*k, v))
+    }
+
     /// Evaluates the polynomial with the given value.
+    ///
+    /// By Horner's method from the highest degree term, raising `value` to the gaps between the
+    /// degrees of consecutive terms by squaring, so that sparse polynomials of high degree take
+    /// time logarithmic in their degrees.
     pub fn eval(&self, value: C) -> C {
+        let mut terms = self.terms().rev().peekable();
         let mut out = C::zero();
-        for (k, v) in &self.coefficients {
-            let mut inner = C::one();
-            for _ in 0..*k {
-                inner = inner.mul(&value);
-            }
-            inner = inner.mul(v);
-            out = out.add(&inner);
+        while let Some((degree, coefficient)) = terms.next() {
+            out = out.add(coefficient);
+            let next = terms.peek().map_or(0, |(next, _)| *next);
+            out = out.mul(&pow(&value, degree - next));
         }
         out
     }
+
+    /// Returns the polynomial raised to `exponent`, by squaring.
+    pub fn pow(&self, exponent: u64) -> Self {
+        pow(self, exponent)
+    }
+}
+
+/// Returns `base^exponent`, by exponentiation by squaring.
+fn pow<T: Semiring>(base: &T, exponent: u64) -> T {
+    let (mut base, mut exponent, mut result) = (base.clone(), exponent, T::one());
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = result.mul(&base);
+        }
+        exponent /= 2;
+        if exponent > 0 {
+            base = base.mul(&base);
+        }
+    }
+    result
+}
+
+impl<C: Field> Polynomial<C> {
+    /// Returns the quotient if `divisor` divides the polynomial, or `None` otherwise.
+    pub fn exact_div(&self, divisor: &Self) -> Option<Self> {
+        match self.div_rem(divisor)? {
+            (quotient, remainder) if remainder == Self::zero() => Some(quotient),
+            _ => None,
+        }
+    }
+
+    /// Returns the polynomial divided by its leading coefficient, so that it becomes 1, or `None`
+    /// for zero.
+    pub fn monic(&self) -> Option<Self> {
+        let inverse = Self::from(self.leading()?.1.inv()?);
+        let mut monic = self.mul(&inverse);
+        let _unused = monic.coefficients.insert(self.leading()?.0, C::one());
+        Some(monic)
+    }
 }
 
 struct FindIter<'s, T: Eq> {
@@ -0,0 +1,301 @@
+//! Multiplication of dense polynomials, given by their coefficients in increasing degree order.
+//!
+//! [`Semiring::mul_coefficients`] selects one of them by the coefficients and their number:
+//!
+//! - [`schoolbook`] multiplies every pair of coefficients, in `O(nm)` time.
+//! - [`karatsuba`] trades one of four half-size products for additions and subtractions, in
+//!   `O(n^1.59)` time. `u64` coefficients are multiplied by it modulo 2^64, as `Wrapping<u64>`.
+//! - [`fft`] multiplies `f64` coefficients by the fast Fourier transform, in `O(n log n)` time but
+//!   with rounding errors.
+//! - [`ntt`] multiplies integers modulo NTT-friendly primes by the number theoretic transform, in
+//!   `O(n log n)` time.
+
+use std::f64::consts::PI;
+use std::ops;
+
+use super::{pow, Field, ModInt, Ring, Semiring};
+
+/// Length of the shorter operand below which [`karatsuba`] falls back to [`schoolbook`].
+pub const KARATSUBA_THRESHOLD: usize = 32;
+
+/// Length of the shorter operand from which [`fft`] and [`ntt`] are faster than [`karatsuba`].
+pub const TRANSFORM_THRESHOLD: usize = 64;
+
+/// Primes `p = c * 2^k + 1` with `k` and a primitive root modulo `p`, i.e., moduli for which [`ntt`]
+/// multiplies polynomials whose product has at most `2^k` coefficients.
+const NTT_PRIMES: [(u64, u32, u64); 5] = [
+    (998_244_353, 23, 3),
+    (469_762_049, 26, 3),
+    (167_772_161, 25, 3),
+    (754_974_721, 24, 11),
+    (18_446_744_069_414_584_321, 32, 7),
+];
+
+/// Adds `values` to the prefix of `out`.
+fn add_into<T: Semiring>(out: &mut [T], values: &[T]) {
+    for (out, value) in out.iter_mut().zip(values) {
+        *out = out.add(value);
+    }
+}
+
+/// Adds `lhs * rhs` to the prefix of `out`.
+fn schoolbook_into<T: Semiring>(lhs: &[T], rhs: &[T], out: &mut [T]) {
+    for (i, a) in lhs.iter().enumerate() {
+        for (out, b) in out[i..].iter_mut().zip(rhs) {
+            *out = out.add(&a.mul(b));
+        }
+    }
+}
+
+/// Multiplies every pair of coefficients.
+pub fn schoolbook<T: Semiring>(lhs: &[T], rhs: &[T]) -> Vec<T> {
+    if lhs.is_empty() || rhs.is_empty() {
+        return Vec::new();
+    }
+    let mut out = vec![T::zero(); lhs.len() + rhs.len() - 1];
+    schoolbook_into(lhs, rhs, &mut out);
+    out
+}
+
+/// Adds `lhs * rhs` to the prefix of `out`.
+fn karatsuba_into<T: Ring>(lhs: &[T], rhs: &[T], out: &mut [T]) {
+    let (short, long) = if lhs.len() <= rhs.len() {
+        (lhs, rhs)
+    } else {
+        (rhs, lhs)
+    };
+    if short.len() < KARATSUBA_THRESHOLD {
+        return schoolbook_into(short, long, out);
+    }
+
+    // Unbalanced operands are multiplied by chunks of the shorter length.
+    if long.len() > short.len() {
+        for (i, chunk) in long.chunks(short.len()).enumerate() {
+            karatsuba_into(short, chunk, &mut out[i * short.len()..]);
+        }
+        return;
+    }
+
+    // (a0 + a1 x^h)(b0 + b1 x^h) = a0 b0 + ((a0 + a1)(b0 + b1) - a0 b0 - a1 b1) x^h + a1 b1 x^2h.
+    let half = short.len() / 2;
+    let (a0, a1) = lhs.split_at(half);
+    let (b0, b1) = rhs.split_at(half);
+    let low = karatsuba(a0, b0);
+    let high = karatsuba(a1, b1);
+    let sum = |x0: &[T], x1: &[T]| {
+        let mut sum = x1.to_vec();
+        add_into(&mut sum, x0);
+        sum
+    };
+    let mut middle = karatsuba(&sum(a0, a1), &sum(b0, b1));
+    for (i, middle) in middle.iter_mut().enumerate() {
+        *middle = middle.sub(&high[i]);
+        if let Some(low) = low.get(i) {
+            *middle = middle.sub(low);
+        }
+    }
+    add_into(out, &low);
+    add_into(&mut out[half..], &middle);
+    add_into(&mut out[2 * half..], &high);
+}
+
+/// Multiplies by Karatsuba's algorithm, which needs subtraction and hence a [`Ring`].
+pub fn karatsuba<T: Ring>(lhs: &[T], rhs: &[T]) -> Vec<T> {
+    if lhs.is_empty() || rhs.is_empty() {
+        return Vec::new();
+    }
+    let mut out = vec![T::zero(); lhs.len() + rhs.len() - 1];
+    karatsuba_into(lhs, rhs, &mut out);
+    out
+}
+
+/// Complex number for [`fft`].
+#[derive(Debug, Clone, Copy)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    /// Returns `e^(i angle)`.
+    fn expi(angle: f64) -> Self {
+        Self {
+            re: angle.cos(),
+            im: angle.sin(),
+        }
+    }
+}
+
+impl ops::Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            re: self.re + rhs.re,
+            im: self.im + rhs.im,
+        }
+    }
+}
+
+impl ops::Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            re: self.re - rhs.re,
+            im: self.im - rhs.im,
+        }
+    }
+}
+
+impl ops::Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            re: self.re * rhs.re - self.im * rhs.im,
+            im: self.re * rhs.im + self.im * rhs.re,
+        }
+    }
+}
+
+/// Permutes `values`, whose length is a power of two, into the bit-reversed order of their indices.
+fn bit_reverse<T>(values: &mut [T]) {
+    let n = values.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+}
+
+/// Transforms `values`, whose length is a power of two, in place by the Cooley-Tukey butterflies,
+/// where `twiddles(len)` returns the powers of a primitive `len`-th root of unity.
+fn transform<T: Copy + ops::Add<Output = T> + ops::Sub<Output = T> + ops::Mul<Output = T>>(
+    values: &mut [T],
+    mut twiddles: impl FnMut(usize) -> Vec<T>,
+) {
+    bit_reverse(values);
+    let mut len = 2;
+    while len <= values.len() {
+        let twiddles = twiddles(len);
+        for block in values.chunks_mut(len) {
+            let (low, high) = block.split_at_mut(len / 2);
+            for ((low, high), twiddle) in low.iter_mut().zip(high).zip(&twiddles) {
+                let (u, v) = (*low, *high * *twiddle);
+                *low = u + v;
+                *high = u - v;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Multiplies by the fast Fourier transform.
+///
+/// Coefficients within the rounding error of zero, relative to the largest possible coefficient of
+/// the product, are rounded to zero.
+pub fn fft(lhs: &[f64], rhs: &[f64]) -> Vec<f64> {
+    if lhs.is_empty() || rhs.is_empty() {
+        return Vec::new();
+    }
+    let len = lhs.len() + rhs.len() - 1;
+    let n = len.next_power_of_two();
+    let complex = |values: &[f64]| {
+        let mut complex = values
+            .iter()
+            .map(|&re| Complex { re, im: 0_f64 })
+            .collect::<Vec<_>>();
+        complex.resize(
+            n,
+            Complex {
+                re: 0_f64,
+                im: 0_f64,
+            },
+        );
+        complex
+    };
+    // The roots are computed directly rather than by repeated multiplication, which accumulates
+    // rounding errors.
+    let twiddles = |sign: f64| {
+        move |len: usize| {
+            (0..len / 2)
+                .map(|j| Complex::expi(sign * 2_f64 * PI * j as f64 / len as f64))
+                .collect()
+        }
+    };
+
+    let (mut a, mut b) = (complex(lhs), complex(rhs));
+    transform(&mut a, twiddles(1_f64));
+    transform(&mut b, twiddles(1_f64));
+    for (a, b) in a.iter_mut().zip(&b) {
+        *a = *a * *b;
+    }
+    transform(&mut a, twiddles(-1_f64));
+
+    let max = |values: &[f64]| values.iter().fold(0_f64, |max, value| max.max(value.abs()));
+    let bound = max(lhs) * max(rhs) * lhs.len().min(rhs.len()) as f64;
+    let tolerance = bound * f64::EPSILON * (n.trailing_zeros() + 1) as f64 * 4_f64;
+    a.iter()
+        .take(len)
+        .map(|value| value.re / n as f64)
+        .map(|value| {
+            if value.abs() <= tolerance {
+                0_f64
+            } else {
+                value
+            }
+        })
+        .collect()
+}
+
+/// Multiplies by the number theoretic transform, i.e., the Fourier transform modulo `M`.
+///
+/// Returns `None` unless `M` is an NTT-friendly prime, e.g. `998244353`, with a primitive root of
+/// unity of order at least the number of coefficients of the product.
+pub fn ntt<const M: u64>(lhs: &[ModInt<M>], rhs: &[ModInt<M>]) -> Option<Vec<ModInt<M>>> {
+    if lhs.is_empty() || rhs.is_empty() {
+        return Some(Vec::new());
+    }
+    let len = lhs.len() + rhs.len() - 1;
+    let n = len.next_power_of_two();
+    let (_, two_adicity, generator) = NTT_PRIMES.iter().find(|(prime, _, _)| *prime == M)?;
+    if n.trailing_zeros() > *two_adicity {
+        return None;
+    }
+    let twiddles = |root: ModInt<M>| {
+        move |len: usize| {
+            let step = pow(&root, (n / len) as u64);
+            let mut twiddles = Vec::with_capacity(len / 2);
+            let mut twiddle = ModInt::one();
+            for _ in 0..len / 2 {
+                twiddles.push(twiddle);
+                twiddle = Semiring::mul(&twiddle, &step);
+            }
+            twiddles
+        }
+    };
+
+    // A primitive n-th root of unity.
+    let root = pow(&ModInt::<M>::new(*generator), (M - 1) / n as u64);
+    let inverse = root.inv()?;
+    let (mut a, mut b) = (lhs.to_vec(), rhs.to_vec());
+    a.resize(n, ModInt::zero());
+    b.resize(n, ModInt::zero());
+    transform(&mut a, twiddles(root));
+    transform(&mut b, twiddles(root));
+    for (a, b) in a.iter_mut().zip(&b) {
+        *a = *a * *b;
+    }
+    transform(&mut a, twiddles(inverse));
+
+    let scale = ModInt::<M>::new(n as u64).inv()?;
+    Some(a.iter().take(len).map(|value| *value * scale).collect())
+}
@@ -88,4 +88,352 @@ mod test {
             )
         );
     }
+
+    fn test_ring<T: Ring>() {
+        // (x - 2)(x + 2) - (x^2 - 4) == 0
+        let two = Polynomial::from(from_isize::<T>(2));
+        let poly = Polynomial::mul(&Polynomial::x().sub(&two), &Polynomial::x().add(&two))
+            .sub(&Polynomial::mul(&Polynomial::x(), &Polynomial::x()).sub(&two.add(&two)));
+        assert_eq!(poly, Polynomial::zero());
+        assert_eq!(
+            Polynomial::<T>::x().neg().eval(from_usize(3)),
+            from_isize(-3)
+        );
+        assert_eq!(from_isize::<T>(-5).add(&from_usize(5)), T::zero());
+    }
+
+    #[test]
+    fn test_ring_i64() {
+        test_ring::<i64>();
+    }
+
+    #[test]
+    fn test_ring_f64() {
+        test_ring::<f64>();
+    }
+
+    #[test]
+    fn test_ring_p_i64() {
+        test_ring::<Polynomial<i64>>();
+    }
+
+    #[test]
+    fn test_field_f64() {
+        assert_eq!(4_f64.inv(), Some(0.25));
+        assert_eq!(Field::div(&3_f64, &4_f64), Some(0.75));
+        assert_eq!(0_f64.inv(), None);
+        assert_eq!(Field::div(&1_f64, &0_f64), None);
+
+        // Dividing by a constant polynomial multiplies by its inverse.
+        let poly = Polynomial::add(&Polynomial::x(), &Polynomial::from(2_f64));
+        let half = Polynomial::from(2_f64.inv().unwrap());
+        assert_eq!(Polynomial::mul(&poly, &half).eval(4_f64), 3_f64);
+    }
+
+    /// Returns the polynomial with the coefficients, from the constant term.
+    fn poly<C: Semiring>(coefficients: &[C]) -> Polynomial<C> {
+        coefficients
+            .iter()
+            .rev()
+            .fold(Polynomial::zero(), |poly, coefficient| {
+                Polynomial::add(
+                    &Polynomial::mul(&poly, &Polynomial::x()),
+                    &Polynomial::from(coefficient.clone()),
+                )
+            })
+    }
+
+    #[test]
+    fn test_div_rem() {
+        // Small integer coefficients and leading coefficients that are powers of two keep the
+        // arithmetic exact.
+        let mut state = 7_u64;
+        let mut next = |n: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % n
+        };
+        for _ in 0..200 {
+            let a = (0..next(8))
+                .map(|_| next(11) as f64 - 5.0)
+                .collect::<Vec<_>>();
+            let mut b = (0..next(4))
+                .map(|_| next(11) as f64 - 5.0)
+                .collect::<Vec<_>>();
+            b.push([1.0, -2.0, 0.5][next(3) as usize]);
+            let (a, b) = (poly(&a), poly(&b));
+            let (q, r) = a.div_rem(&b).unwrap();
+            assert_eq!(Polynomial::add(&Polynomial::mul(&q, &b), &r), a);
+            assert!(r.degree() < b.degree());
+            assert_eq!(Polynomial::mul(&a, &b).exact_div(&b), Some(a.clone()));
+        }
+        assert!(poly(&[1.0, 2.0]).div_rem(&Polynomial::zero()).is_none());
+        assert_eq!(poly(&[1.0, 0.0, 1.0]).exact_div(&poly(&[1.0, 1.0])), None);
+        assert_eq!(
+            Polynomial::zero().exact_div(&poly(&[1.0, 1.0])),
+            Some(Polynomial::zero())
+        );
+
+        // (x^2 - 1) / (x - 1) = x + 1, and 2x^2 + 4 = 2 (x^2 + 2).
+        assert_eq!(
+            poly(&[-1.0, 0.0, 1.0]).exact_div(&poly(&[-1.0, 1.0])),
+            Some(poly(&[1.0, 1.0]))
+        );
+        assert_eq!(poly(&[4.0, 0.0, 2.0]).monic(), Some(poly(&[2.0, 0.0, 1.0])));
+        assert_eq!(Polynomial::<f64>::zero().monic(), None);
+
+        // Integers divide with a non-negative remainder.
+        assert_eq!((-7_i64).div_rem(&2), Some((-4, 1)));
+        assert_eq!(7_i64.div_rem(&0), None);
+        assert_eq!(i64::MIN.div_rem(&-1), None);
+    }
+
+    #[test]
+    fn test_gcd() {
+        use super::super::assignment02;
+
+        // It agrees with assignment 2 up to sign, and its coefficients satisfy Bezout's identity.
+        // Dividing `i64::MIN` by -1 and the cofactors of `i64::MIN` overflow, so they are avoided.
+        for (a, b) in [
+            (240_i64, 46),
+            (-240, 46),
+            (17, -5),
+            (0, 9),
+            (9, 0),
+            (0, 0),
+            (i64::MIN, -1),
+            (-1, i64::MIN),
+            (i64::MIN, i64::MIN),
+            (i64::MIN, i64::MAX),
+            (i64::MIN, 6),
+        ] {
+            let g = gcd(&a, &b);
+            assert_eq!(
+                g.unsigned_abs(),
+                assignment02::gcd(a.unsigned_abs(), b.unsigned_abs())
+            );
+            let (g, x, y) = extended_gcd(&a, &b);
+            assert_eq!(
+                a as i128 * x as i128 + b as i128 * y as i128,
+                g as i128,
+                "{} {}",
+                a,
+                b
+            );
+        }
+
+        // gcd((x - 1)(x + 2), (x - 1)(x + 3)) = x - 1.
+        let a = poly(&[-2.0, 1.0, 1.0]);
+        let b = poly(&[-3.0, 2.0, 1.0]);
+        assert_eq!(gcd(&a, &b).monic(), Some(poly(&[-1.0, 1.0])));
+        let (g, x, y) = extended_gcd(&a, &b);
+        assert_eq!(
+            Polynomial::add(&Polynomial::mul(&a, &x), &Polynomial::mul(&b, &y)),
+            g
+        );
+        // Coprime polynomials have a constant gcd.
+        assert_eq!(
+            gcd(&poly(&[1.0, 0.0, 1.0]), &poly(&[1.0, 1.0])).degree(),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_accessors_and_eval() {
+        // 3x^4 - 2x + 1
+        let poly = poly(&[1.0, -2.0, 0.0, 0.0, 3.0]);
+        assert_eq!(poly.degree(), Some(4));
+        assert_eq!(poly.leading_coefficient(), Some(&3.0));
+        assert_eq!(
+            (0..6).map(|k| poly.coefficient(k)).collect::<Vec<_>>(),
+            vec![1.0, -2.0, 0.0, 0.0, 3.0, 0.0]
+        );
+        assert_eq!(
+            poly.terms().collect::<Vec<_>>(),
+            vec![(0, &1.0), (1, &-2.0), (4, &3.0)]
+        );
+        assert_eq!(poly.eval(2.0), 45.0);
+        assert_eq!(Polynomial::<f64>::zero().degree(), None);
+        assert_eq!(Polynomial::<f64>::zero().leading_coefficient(), None);
+        assert_eq!(Polynomial::from(0_i64), Polynomial::zero());
+
+        // Sparse polynomials of huge degree take a logarithmic number of multiplications.
+        let sparse = Polynomial::add(
+            &Polynomial::monomial(1_i64, 1 << 40),
+            &Polynomial::monomial(1_i64, 3),
+        );
+        assert_eq!(sparse.eval(1), 2);
+        assert_eq!(sparse.eval(-1), 0);
+        assert_eq!(Polynomial::monomial(1_u64, 63).eval(2), 1 << 63);
+
+        // Evaluation agrees with summing the terms.
+        let dense = (0..50).fold(Polynomial::zero(), |poly, k| {
+            Polynomial::add(&poly, &Polynomial::monomial(k as i64 - 25, k))
+        });
+        for x in [-2_i64, -1, 0, 1, 2] {
+            let expected = dense
+                .terms()
+                .map(|(degree, coefficient)| coefficient * x.pow(degree as u32))
+                .sum::<i64>();
+            assert_eq!(dense.eval(x), expected);
+        }
+    }
+
+    #[test]
+    fn test_multiplication() {
+        use super::super::assignment06::multiplication::*;
+
+        let mut state = 11_u64;
+        let mut next = |n: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % n
+        };
+        // Lengths around the thresholds, and unbalanced ones.
+        let lengths = [0, 1, 2, 31, 32, 33, 63, 64, 65, 100, 257, 600];
+
+        // Karatsuba agrees with schoolbook on integers.
+        for &n in &lengths {
+            for &m in &lengths {
+                let a = (0..n).map(|_| next(2001) as i64 - 1000).collect::<Vec<_>>();
+                let b = (0..m).map(|_| next(2001) as i64 - 1000).collect::<Vec<_>>();
+                assert_eq!(karatsuba(&a, &b), schoolbook(&a, &b), "{} x {}", n, m);
+            }
+        }
+
+        // So it does on unsigned integers, whose differences wrap around.
+        for &n in &lengths {
+            for &m in &lengths {
+                let a = (0..n).map(|_| next(1001)).collect::<Vec<_>>();
+                let b = (0..m).map(|_| next(1001)).collect::<Vec<_>>();
+                assert_eq!(
+                    u64::mul_coefficients(&a, &b),
+                    schoolbook(&a, &b),
+                    "{} x {}",
+                    n,
+                    m
+                );
+            }
+        }
+        // (-1) * (-1) = 1 modulo 2^64, even though the middle products overflow, and dense and
+        // sparse polynomials overflow alike.
+        let a = vec![u64::MAX; 40];
+        assert_eq!(
+            u64::mul_coefficients(&a, &a),
+            (1..80_u64).map(|k| k.min(80 - k)).collect::<Vec<_>>()
+        );
+        assert_eq!(schoolbook(&a, &a), u64::mul_coefficients(&a, &a));
+        let sparse = Polynomial::add(
+            &Polynomial::from(u64::MAX),
+            &Polynomial::monomial(u64::MAX, 1000),
+        );
+        let square = [(0, 1), (1000, 2), (2000, 1)].iter().fold(
+            Polynomial::zero(),
+            |square, &(degree, coefficient)| {
+                Polynomial::add(&square, &Polynomial::monomial(coefficient, degree))
+            },
+        );
+        assert_eq!(Polynomial::mul(&sparse, &sparse), square);
+        assert_eq!(sparse.eval(2), u64::MAX);
+
+        // NTT agrees with schoolbook modulo NTT-friendly primes, and does not apply to others.
+        fn residues<const M: u64>(next: &mut impl FnMut(u64) -> u64, n: usize) -> Vec<ModInt<M>> {
+            (0..n)
+                .map(|_| ModInt::new(next(u32::MAX as u64) * next(u32::MAX as u64)))
+                .collect()
+        }
+        for &n in &lengths {
+            let a = residues::<998_244_353>(&mut next, n);
+            let b = residues::<998_244_353>(&mut next, 600 - n);
+            assert_eq!(ntt(&a, &b).unwrap(), schoolbook(&a, &b));
+            assert_eq!(karatsuba(&a, &b), schoolbook(&a, &b));
+            let a = residues::<18_446_744_069_414_584_321>(&mut next, n);
+            let b = residues::<18_446_744_069_414_584_321>(&mut next, n);
+            assert_eq!(ntt(&a, &b).unwrap(), schoolbook(&a, &b));
+        }
+        let a = residues::<1_000_000_007>(&mut next, 100);
+        assert_eq!(ntt(&a, &a), None);
+        assert_eq!(
+            Polynomial::mul(&poly(&a), &poly(&a)),
+            poly(&schoolbook(&a, &a))
+        );
+        assert_eq!(ModInt::<7>::new(3).inv(), Some(ModInt::new(5)));
+        assert_eq!(ModInt::<8>::new(2).inv(), None);
+        assert_eq!(ModInt::<7>::new(3).neg(), ModInt::new(4));
+
+        // FFT agrees with schoolbook up to rounding, and rounds cancelled coefficients to zero.
+        for &n in &lengths {
+            let a = (0..n).map(|_| next(11) as f64 - 5.0).collect::<Vec<_>>();
+            let b = (0..600 - n)
+                .map(|_| next(11) as f64 - 5.0)
+                .collect::<Vec<_>>();
+            for (x, y) in fft(&a, &b).iter().zip(schoolbook(&a, &b)) {
+                assert!((x - y).abs() < 1e-9, "{} != {}", x, y);
+            }
+        }
+        assert_eq!(fft(&[1.0, 1.0], &[-1.0, 1.0])[1], 0.0);
+        // Products of even polynomials have no odd terms.
+        let even = (0..200)
+            .map(|i| [next(11) as f64 - 5.0, 0.0][i % 2])
+            .collect::<Vec<_>>();
+        let product = Polynomial::mul(&poly(&even), &poly(&even));
+        assert!(product.terms().all(|(degree, _)| degree % 2 == 0));
+
+        // Dense polynomials are multiplied by the selected algorithm, and sparse ones term by term.
+        let a = (0..300).map(|_| next(21) as i64 - 10).collect::<Vec<_>>();
+        let b = (0..200).map(|_| next(21) as i64 - 10).collect::<Vec<_>>();
+        let product = Polynomial::mul(&poly(&a), &poly(&b));
+        assert_eq!(product, poly(&schoolbook(&a, &b)));
+        let shifted = Polynomial::mul(&Polynomial::monomial(1, 1 << 40), &poly(&b));
+        assert_eq!(
+            Polynomial::mul(&shifted, &poly(&a)),
+            Polynomial::mul(&Polynomial::monomial(1, 1 << 40), &product)
+        );
+    }
+
+    #[test]
+    fn test_operators() {
+        // x^2 + 5x + 6, as in `test_polynomial`.
+        let x = Polynomial::<i64>::x();
+        let poly = x.clone() * x.clone() + 5 * x.clone() + 6;
+        assert_eq!(poly, self::poly(&[6, 5, 1]));
+        assert_eq!(
+            poly,
+            Polynomial::add(
+                &Polynomial::add(
+                    &Polynomial::mul(&Polynomial::x(), &Polynomial::x()),
+                    &Polynomial::mul(&Polynomial::from(5), &Polynomial::x()),
+                ),
+                &Polynomial::from(6),
+            )
+        );
+
+        // Borrowed operands, which are not consumed.
+        let (a, b) = (&x + 2, &x + 3);
+        assert_eq!(&a * &b, poly);
+        assert_eq!(&a * b.clone(), a.clone() * &b);
+        assert_eq!(&b - &a, Polynomial::one());
+        assert_eq!(-&a, -1 * a.clone());
+        assert_eq!(-(a.clone() - b), Polynomial::one());
+        assert_eq!(2 - &x, -(x.clone() - 2));
+        assert_eq!(&a * 3, 3 * &a);
+
+        // Powers, including sparse ones of high degree.
+        assert_eq!(a.pow(0), Polynomial::one());
+        assert_eq!(a.pow(3), &a * &a * &a);
+        assert_eq!(a.pow(5).eval(1), 243);
+        assert_eq!(x.pow(1 << 40), Polynomial::monomial(1, 1 << 40));
+
+        // Other coefficients.
+        let y = Polynomial::<f64>::x();
+        assert_eq!((0.5 * &y - 1.0).eval(4.0), 1.0);
+        assert_eq!((2 * Polynomial::<u64>::x() + 1).pow(2).eval(1), 9);
+        let z = Polynomial::<ModInt<7>>::x();
+        assert_eq!(
+            (ModInt::new(3) * &z + ModInt::new(5)).pow(7),
+            ModInt::new(3) * z.pow(7) + ModInt::new(5)
+        );
+    }
 }
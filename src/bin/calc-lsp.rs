@@ -0,0 +1,11 @@
+use std::io;
+
+use anyhow::Result;
+
+use cs220::assignments::assignment04::lsp;
+
+fn main() -> Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    lsp::serve(stdin.lock(), stdout.lock())
+}
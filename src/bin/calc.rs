@@ -1,8 +1,8 @@
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, BufRead, Read};
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
 
 use cs220::assignments::assignment04::*;
 
@@ -40,31 +40,268 @@ impl<'a> BufRead for Input<'a> {
     }
 }
 
+/// Output format of the calculated commands.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Render {
+    /// `v = 3`.
+    Text,
+    /// `v = \frac{6}{2} = 3`.
+    Latex,
+    /// `<math><mi>v</mi><mo>=</mo>...</math>`.
+    Mathml,
+}
+
+/// Characters plots are drawn with.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum PlotStyle {
+    /// Braille patterns.
+    Braille,
+    /// Half blocks.
+    Block,
+}
+
+/// What happens when an integer does not fit in its type.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Overflow {
+    /// Wraps around.
+    Wrapping,
+    /// Fails.
+    Checked,
+    /// Clamps to the type's range.
+    Saturating,
+}
+
+/// Kind of values a session computes with, which `:mod <n>` and `:mod off` switch between.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    /// The values chosen by the arguments.
+    Default,
+    /// Residues modulo the modulus.
+    Modular(u64),
+}
+
+/// Parses the argument of `:mod`, i.e., `off` or a modulus from 2 to 2^53, which literals can
+/// represent exactly.
+fn mode(argument: &str) -> Result<Mode> {
+    if argument == "off" {
+        return Ok(Mode::Default);
+    }
+    match argument.parse::<u64>() {
+        Ok(modulus) if (2..=1 << 53).contains(&modulus) => Ok(Mode::Modular(modulus)),
+        _ => bail!("modulus `{}` is not an integer from 2 to 2^53", argument),
+    }
+}
+
+/// Parses the name of an integer type.
+fn int_type(name: &str) -> Result<integer::IntType, String> {
+    integer::IntType::from_name(name).ok_or_else(|| format!("unknown integer type `{}`", name))
+}
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
     /// Expression filepath.
     #[clap(value_parser)]
     filepath: Option<String>,
+
+    /// Propagates measurement uncertainties (`value +/- sigma`).
+    #[clap(long)]
+    uncertain: bool,
+
+    /// Computes with integers of the type (`u8`, ..., `u64`, `usize`, `i8`, ..., `i64`, `isize`),
+    /// which literals without a suffix like `255u8` have.
+    #[clap(long, value_parser = int_type)]
+    int: Option<integer::IntType>,
+
+    /// What happens when an integer does not fit in its type.
+    #[clap(long, value_enum, default_value_t = Overflow::Wrapping)]
+    overflow: Overflow,
+
+    /// Evaluates the spreadsheet in the CSV file, whose cells are numbers, text, or formulas like
+    /// `=A1 + sum(B1:B3)`, and prints it as CSV.
+    #[clap(long, value_parser)]
+    sheet: Option<String>,
+
+    /// Output format.
+    #[clap(long, value_enum, default_value_t = Render::Text)]
+    render: Render,
+
+    /// Characters `plot(expr, x, a, b)` draws with.
+    #[clap(long, value_enum, default_value_t = PlotStyle::Braille)]
+    plot_style: PlotStyle,
+
+    /// Also writes each plot as SVG to the file, overwriting it.
+    #[clap(long, value_parser)]
+    svg: Option<String>,
+}
+
+/// Prints the calculated command in the given format.
+fn print<V: context::Value>(
+    render: Render,
+    variable: &str,
+    expression: &syntax::Expression,
+    value: &V,
+) {
+    let variable = syntax::Expression::Variable(variable.to_string());
+    match render {
+        Render::Text => println!("{} = {}", variable, value),
+        Render::Latex => println!(
+            "{} = {} = {}",
+            render::latex(&variable),
+            render::latex(expression),
+            value
+        ),
+        Render::Mathml => println!(
+            "<math display=\"block\">{}<mo>=</mo>{}<mo>=</mo><mn>{}</mn></math>",
+            render::mathml_row(&variable),
+            render::mathml_row(expression),
+            value
+        ),
+    }
+}
+
+/// Draws the plot in the style chosen by the arguments.
+fn draw_plot(plot: &plot::Plot, options: &Args) -> Result<()> {
+    let style = match options.plot_style {
+        PlotStyle::Braille => plot::Style::Braille,
+        PlotStyle::Block => plot::Style::Block,
+    };
+    print!(
+        "{}",
+        plot.render(style, plot::DEFAULT_WIDTH, plot::DEFAULT_HEIGHT)
+    );
+    if let Some(path) = &options.svg {
+        fs::write(path, plot.svg(640_f64, 400_f64))?;
+    }
+    Ok(())
+}
+
+/// Leaves the expression as is.
+fn unchanged<V>(
+    expression: &syntax::Expression,
+    _context: &context::Context<V>,
+) -> Result<syntax::Expression> {
+    Ok(expression.clone())
+}
+
+/// Runs the commands in the input. `prepare` rewrites each expression before it is calculated.
+///
+/// Stops at `:mod <n>` or `:mod off`, returning the mode to continue the input in, or at the end of
+/// the input, returning `None`.
+fn run<V: context::Value>(
+    input: &mut Input<'_>,
+    mut context: context::Context<V>,
+    options: &Args,
+    prepare: impl Fn(&syntax::Expression, &context::Context<V>) -> Result<syntax::Expression>,
+) -> Result<Option<Mode>> {
+    for line in input.by_ref().lines() {
+        match session::run_line(&mut context, &line?, &prepare)? {
+            session::Outcome::Blank => {}
+            session::Outcome::Report(lines) => {
+                for line in lines {
+                    println!("{}", line);
+                }
+            }
+            session::Outcome::Mode(argument) => return mode(&argument).map(Some),
+            session::Outcome::Plot(plot) => draw_plot(&plot, options)?,
+            session::Outcome::Expansion(expansion) => println!("{}", expansion),
+            session::Outcome::Calculated {
+                command,
+                variable,
+                value,
+            } => {
+                print(options.render, &variable, &command.expression, &value);
+                if let Some(warning) = value.warning() {
+                    eprintln!("warning: {}", warning);
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the context of lists, functions, and dates, with the statistical and financial
+/// functions.
+fn dynamic_context() -> context::Context<context::Dynamic> {
+    let mut context = context::Context::default();
+    let _ = statistics::register(&mut context);
+    let _ = calendar::register(&mut context);
+    let _ = finance::register(&mut context);
+    context
+}
+
+/// Evaluates the spreadsheet, printing it to stdout and the errors of its cells to stderr.
+fn run_sheet(path: &str, context: &context::Context<context::Dynamic>) -> Result<()> {
+    let sheet = sheet::Sheet::from_csv(&fs::read_to_string(path)?)?;
+    let values = sheet.evaluate(context);
+    print!("{}", sheet::write_csv(&values));
+    for (row, cells) in values.iter().enumerate() {
+        for (column, value) in cells.iter().enumerate() {
+            if let sheet::Evaluated::Error(error) = value {
+                eprintln!("{}: {}", sheet::CellRef { row, column }, error);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs the commands in the input with the values chosen by the arguments.
+fn run_default(input: &mut Input<'_>, args: &Args) -> Result<Option<Mode>> {
+    if let Some(ty) = args.int {
+        let overflow = match args.overflow {
+            Overflow::Wrapping => integer::Overflow::Wrapping,
+            Overflow::Checked => integer::Overflow::Checked,
+            Overflow::Saturating => integer::Overflow::Saturating,
+        };
+        let mut context = context::Context::<integer::Int>::default();
+        let _ = integer::register(&mut context, overflow);
+        run(input, context, args, |expression, context| {
+            integer::infer_types(expression, ty, context)
+        })
+    } else if args.uncertain {
+        run(
+            input,
+            context::Context::<uncertain::Uncertain>::default(),
+            args,
+            unchanged,
+        )
+    } else {
+        run(input, dynamic_context(), args, unchanged)
+    }
 }
 
 fn main() -> Result<()> {
     // Parses arguments.
     let args = Args::parse();
 
+    if let Some(path) = &args.sheet {
+        return run_sheet(path, &dynamic_context());
+    }
+
     let stdin = io::stdin();
-    let input = if let Some(filepath) = args.filepath {
-        Input::file(&filepath)?
+    let mut input = if let Some(filepath) = &args.filepath {
+        Input::file(filepath)?
     } else {
         Input::console(&stdin)
     };
 
-    let mut context = context::Context::new();
-    for line in input.lines() {
-        let command = parser::parse_command(&line?)?;
-        let (variable, value) = context.calc_command(&command)?;
-        println!("{} = {}", variable, value);
+    // Each switch of mode starts a new session, without the variables of the previous one.
+    let mut mode = Mode::Default;
+    loop {
+        let next = match mode {
+            Mode::Default => run_default(&mut input, &args)?,
+            Mode::Modular(modulus) => {
+                let mut context = context::Context::<modular::Modular>::default();
+                let _ = modular::register(&mut context);
+                run(&mut input, context, &args, |expression, _| {
+                    modular::with_modulus(expression, modulus)
+                })?
+            }
+        };
+        match next {
+            Some(next) => mode = next,
+            None => return Ok(()),
+        }
     }
-
-    Ok(())
 }